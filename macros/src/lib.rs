@@ -0,0 +1,284 @@
+//! `protocol!` generates the Chrome DevTools Protocol bindings in place, for crates
+//! that can't have a build script.
+
+use std::env;
+use std::fs;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+
+use auto_generate_cdp::{Generator, TimeCrate};
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::parse::{Parse, ParseStream};
+use syn::{bracketed, parse_macro_input, Ident, LitBool, LitStr, Token};
+
+/// Expands to the `cdp` module that `auto_generate_cdp` would write to
+/// `protocol.rs`, or to a compile error if the protocol can't be generated.
+///
+/// ```ignore
+/// auto_generate_cdp_macros::protocol! {
+///     json = "protocol/",
+///     domains = [Page, Runtime],
+/// }
+/// ```
+///
+/// * `json`: a protocol file, a directory whose `.json` and `.pdl` files are used or
+///   a list of them, relative to the crate invoking the macro. `.pdl` files are parsed
+///   as the protocol definition language. Without it the protocol is fetched at `commit`.
+///   Every file read, including the ones `.pdl` files include, is tracked, but files
+///   added to a directory are only picked up when the invoking crate is rebuilt.
+/// * `commit`: commit of ChromeDevTools/devtools-protocol to fetch.
+/// * `domains`: only generate these domains and the ones they depend on.
+/// * `borrowed`: whether to generate the types borrowing from the input, see
+///   `Generator::borrowed`.
+/// * `raw_events`: whether to generate `RawEvent`, see `Generator::raw_events`.
+/// * `time_types`: whether to generate the wrappers of the types holding times, see
+///   `Generator::time_types`.
+/// * `time_crates`: `chrono` and/or `time`, whose types the time types convert to as
+///   well, see `Generator::time_crate`. Implies `time_types = true`.
+#[proc_macro]
+pub fn protocol(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let args = parse_macro_input!(input as Args);
+
+    expand(args)
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
+
+#[derive(Default)]
+struct Args {
+    commit: Option<LitStr>,
+    json: Vec<LitStr>,
+    domains: Vec<Ident>,
+    borrowed: Option<LitBool>,
+    raw_events: Option<LitBool>,
+    time_types: Option<LitBool>,
+    time_crates: Vec<Ident>,
+}
+
+impl Parse for Args {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut args = Args::default();
+
+        while !input.is_empty() {
+            let key: Ident = input.parse()?;
+            input.parse::<Token![=]>()?;
+
+            match key.to_string().as_str() {
+                "commit" => args.commit = Some(input.parse()?),
+                "json" if input.peek(syn::token::Bracket) => {
+                    let content;
+                    bracketed!(content in input);
+                    args.json = content
+                        .parse_terminated(<LitStr as Parse>::parse, Token![,])?
+                        .into_iter()
+                        .collect();
+                }
+                "json" => args.json = vec![input.parse()?],
+                "domains" => {
+                    let content;
+                    bracketed!(content in input);
+                    args.domains = content
+                        .parse_terminated(Ident::parse, Token![,])?
+                        .into_iter()
+                        .collect();
+                }
+                "borrowed" => args.borrowed = Some(input.parse()?),
+                "raw_events" => args.raw_events = Some(input.parse()?),
+                "time_types" => args.time_types = Some(input.parse()?),
+                "time_crates" => {
+                    let content;
+                    bracketed!(content in input);
+                    args.time_crates = content
+                        .parse_terminated(Ident::parse, Token![,])?
+                        .into_iter()
+                        .collect();
+                }
+                _ => {
+                    return Err(syn::Error::new(
+                        key.span(),
+                        "expected `commit`, `json`, `domains`, `borrowed`, `raw_events`, \
+                         `time_types` or `time_crates`",
+                    ))
+                }
+            }
+
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+
+        Ok(args)
+    }
+}
+
+fn expand(args: Args) -> syn::Result<TokenStream> {
+    let mut generator = Generator::new();
+    let mut files = Vec::new();
+
+    if let Some(commit) = &args.commit {
+        generator = generator.commit(&commit.value());
+    }
+    for json in &args.json {
+        files.extend(json_files(json)?);
+    }
+    if !files.is_empty() {
+        generator = generator.files(&files);
+    }
+    if !args.domains.is_empty() {
+        generator = generator.domains(args.domains.iter().map(Ident::to_string));
+    }
+    if let Some(borrowed) = &args.borrowed {
+        generator = generator.borrowed(borrowed.value);
+    }
+    if let Some(raw_events) = &args.raw_events {
+        generator = generator.raw_events(raw_events.value);
+    }
+    if let Some(time_types) = &args.time_types {
+        generator = generator.time_types(time_types.value);
+    }
+    for time_crate in &args.time_crates {
+        let time_crate = match time_crate.to_string().as_str() {
+            "chrono" => TimeCrate::Chrono,
+            "time" => TimeCrate::Time,
+            _ => {
+                return Err(syn::Error::new(
+                    time_crate.span(),
+                    "expected `chrono` or `time`",
+                ))
+            }
+        };
+        generator = generator.time_types(true).time_crate(time_crate);
+    }
+
+    let (cdp, files) = generate(&generator)?;
+
+    // makes rustc recompile the invoking crate when the protocol files change
+    let files = files.iter().map(|file| file.to_string_lossy().into_owned());
+
+    Ok(quote! {
+        #cdp
+
+        #(const _: &[u8] = include_bytes!(#files);)*
+    })
+}
+
+/// Runs `generator`, turning the panics it reports bad protocols with into an error
+/// at the invocation instead of a crashed macro.
+fn generate(generator: &Generator) -> syn::Result<(TokenStream, Vec<PathBuf>)> {
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+    let generated = panic::catch_unwind(AssertUnwindSafe(|| generator.to_tokens_with_files()));
+    panic::set_hook(hook);
+
+    generated.map_err(|payload| {
+        let message = payload
+            .downcast_ref::<String>()
+            .map(String::as_str)
+            .or_else(|| payload.downcast_ref::<&str>().copied())
+            .unwrap_or("the generator panicked");
+        syn::Error::new(Span::call_site(), message)
+    })
+}
+
+/// The protocol files `json` refers to, relative to the invoking crate.
+fn json_files(json: &LitStr) -> syn::Result<Vec<PathBuf>> {
+    let manifest_dir = env::var_os("CARGO_MANIFEST_DIR")
+        .ok_or_else(|| syn::Error::new(json.span(), "CARGO_MANIFEST_DIR is not set"))?;
+    let path = Path::new(&manifest_dir).join(json.value());
+
+    if !path.is_dir() {
+        return if path.is_file() {
+            Ok(vec![path])
+        } else {
+            Err(syn::Error::new(
+                json.span(),
+                format!("{} does not exist", path.display()),
+            ))
+        };
+    }
+
+    let mut files: Vec<PathBuf> = fs::read_dir(&path)
+        .map_err(|err| syn::Error::new(json.span(), err))?
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|file| {
+            file.extension()
+                .is_some_and(|extension| extension == "json" || extension == "pdl")
+        })
+        .collect();
+    files.sort();
+
+    if files.is_empty() {
+        return Err(syn::Error::new(
+            json.span(),
+            format!("{} contains no .json or .pdl files", path.display()),
+        ));
+    }
+
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand_str(input: &str) -> syn::Result<TokenStream> {
+        expand(syn::parse_str(input).unwrap())
+    }
+
+    /// A directory of its own for `files`, written as `(name, contents)`.
+    fn protocol_dir(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = env::temp_dir().join(name);
+        if dir.exists() {
+            fs::remove_dir_all(&dir).unwrap();
+        }
+        fs::create_dir_all(&dir).unwrap();
+        for (file_name, contents) in files {
+            fs::write(dir.join(file_name), contents).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn tracks_included_files() {
+        let dir = protocol_dir(
+            "auto_generate_cdp_macros_include",
+            &[
+                (
+                    "main.pdl",
+                    "version\n  major 1\n  minor 3\n\ninclude other.pdl\n",
+                ),
+                ("other.pdl", "domain Other\n  command ping\n"),
+            ],
+        );
+        let input = format!("json = {:?}", dir.join("main.pdl").display().to_string());
+
+        let tokens = expand_str(&input).unwrap().to_string();
+        for file_name in ["main.pdl", "other.pdl"] {
+            let file = dir.join(file_name).display().to_string();
+            assert!(tokens.contains(&quote!(include_bytes!(#file)).to_string()));
+        }
+    }
+
+    #[test]
+    fn reports_generator_errors() {
+        let dir = protocol_dir(
+            "auto_generate_cdp_macros_errors",
+            &[
+                ("bad.pdl", "domain Bad\n  command\n"),
+                (
+                    "good.pdl",
+                    "version\n  major 1\n  minor 3\n\ndomain Good\n  command ping\n",
+                ),
+            ],
+        );
+        let json = |file_name: &str| format!("{:?}", dir.join(file_name).display().to_string());
+
+        let err = expand_str(&format!("json = {}", json("bad.pdl"))).unwrap_err();
+        assert!(err.to_string().contains("bad.pdl:2"), "{}", err);
+
+        let err =
+            expand_str(&format!("json = {}, domains = [Nope]", json("good.pdl"))).unwrap_err();
+        assert_eq!(err.to_string(), "unknown domain `Nope`");
+    }
+}
@@ -0,0 +1,73 @@
+auto_generate_cdp_macros::protocol! {
+    json = ["../json/js_protocol.json", "../json/browser_protocol.json"],
+    domains = [Page],
+    borrowed = true,
+}
+
+use std::borrow::Cow;
+
+use cdp::types::{Event, Method, RawEvent};
+use cdp::Page;
+
+#[test]
+fn borrows_unescaped_strings() {
+    let message =
+        r#"{ "method": "Page.frameDetached", "params": { "frameId": "1", "reason": "remove" } }"#;
+    match serde_json::from_str::<Event>(message).unwrap() {
+        Event::PageFrameDetached(event) => {
+            assert!(matches!(event.params.frame_id, Cow::Borrowed("1")));
+        }
+        event => panic!("unexpected {:?}", event),
+    }
+
+    // escapes have to be resolved into a new string
+    let message =
+        r#"{ "method": "Page.frameDetached", "params": { "frameId": "1\n", "reason": "remove" } }"#;
+    match serde_json::from_str::<Event>(message).unwrap() {
+        Event::PageFrameDetached(event) => {
+            assert!(matches!(event.params.frame_id, Cow::Owned(ref id) if id == "1\n"));
+        }
+        event => panic!("unexpected {:?}", event),
+    }
+}
+
+#[test]
+fn decodes_raw_events_lazily() {
+    let message = r#"{ "method": "Page.frameDetached", "sessionId": "A", "params": { "frameId": "1", "reason": "remove" } }"#;
+    let event: RawEvent = serde_json::from_str(message).unwrap();
+    assert_eq!(event.kind(), "Page.frameDetached");
+    assert!(matches!(event.session_id, Some(Cow::Borrowed("A"))));
+
+    let detached = event.decode::<Page::events::FrameDetachedEvent>().unwrap();
+    assert!(matches!(detached.params.frame_id, Cow::Borrowed("1")));
+    assert!(event.decode::<Page::events::FrameAttachedEvent>().is_err());
+}
+
+#[test]
+fn round_trips_return_objects() {
+    let response = r#"{"frameId":"F","loaderId":"L\"1"}"#;
+    let returns: <Page::Navigate as Method>::ReturnObject<'_> =
+        serde_json::from_str(response).unwrap();
+    assert!(matches!(returns.frame_id, Cow::Borrowed("F")));
+    assert!(matches!(returns.loader_id, Some(Cow::Owned(ref id)) if id == "L\"1"));
+    assert_eq!(returns.error_text, None);
+
+    assert_eq!(serde_json::to_string(&returns).unwrap(), response);
+}
+
+#[test]
+fn borrows_json_values() {
+    let message = r#"{ "method": "Runtime.inspectRequested", "params": { "object": { "type": "object" }, "hints": { "queryObjects": true } } }"#;
+    match serde_json::from_str::<Event>(message).unwrap() {
+        Event::RuntimeInspectRequested(event) => {
+            assert_eq!(event.params.hints["queryObjects"].get(), "true");
+        }
+        event => panic!("unexpected {:?}", event),
+    }
+
+    let message = r#"{ "method": "Runtime.inspectRequested", "params": { "object": { "type": "object" }, "hints": null } }"#;
+    match serde_json::from_str::<Event>(message).unwrap() {
+        Event::RuntimeInspectRequested(event) => assert!(event.params.hints.is_empty()),
+        event => panic!("unexpected {:?}", event),
+    }
+}
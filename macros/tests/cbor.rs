@@ -0,0 +1,136 @@
+auto_generate_cdp_macros::protocol! {
+    json = ["../json/js_protocol.json", "../json/browser_protocol.json"],
+    domains = [Page],
+}
+
+use cdp::types::{cbor, Event, Method};
+use cdp::Page;
+use serde::Deserialize;
+
+// The golden messages are what Chromium's crdtp converts their JSON to.
+
+/// `{"method":"Page.navigate","id":1,"params":{"url":"https://example.com"}}`
+const NAVIGATE: &[u8] = &[
+    0xd8, 0x18, 0x5a, 0x00, 0x00, 0x00, 0x43, 0xbf, 0x66, 0x6d, 0x65, 0x74, 0x68, 0x6f, 0x64, 0x6d,
+    0x50, 0x61, 0x67, 0x65, 0x2e, 0x6e, 0x61, 0x76, 0x69, 0x67, 0x61, 0x74, 0x65, 0x62, 0x69, 0x64,
+    0x01, 0x66, 0x70, 0x61, 0x72, 0x61, 0x6d, 0x73, 0xd8, 0x18, 0x5a, 0x00, 0x00, 0x00, 0x1a, 0xbf,
+    0x63, 0x75, 0x72, 0x6c, 0x73, 0x68, 0x74, 0x74, 0x70, 0x73, 0x3a, 0x2f, 0x2f, 0x65, 0x78, 0x61,
+    0x6d, 0x70, 0x6c, 0x65, 0x2e, 0x63, 0x6f, 0x6d, 0xff, 0xff,
+];
+
+/// `{"id":1,"result":{"frameId":"F","loaderId":"L"}}`
+const NAVIGATE_RESPONSE: &[u8] = &[
+    0xd8, 0x18, 0x5a, 0x00, 0x00, 0x00, 0x2b, 0xbf, 0x62, 0x69, 0x64, 0x01, 0x66, 0x72, 0x65, 0x73,
+    0x75, 0x6c, 0x74, 0xd8, 0x18, 0x5a, 0x00, 0x00, 0x00, 0x17, 0xbf, 0x67, 0x66, 0x72, 0x61, 0x6d,
+    0x65, 0x49, 0x64, 0x61, 0x46, 0x68, 0x6c, 0x6f, 0x61, 0x64, 0x65, 0x72, 0x49, 0x64, 0x61, 0x4c,
+    0xff, 0xff,
+];
+
+/// `{"method":"Page.frameDetached","params":{"frameId":"1","reason":"remove"}}`
+const FRAME_DETACHED: &[u8] = &[
+    0xd8, 0x18, 0x5a, 0x00, 0x00, 0x00, 0x44, 0xbf, 0x66, 0x6d, 0x65, 0x74, 0x68, 0x6f, 0x64, 0x72,
+    0x50, 0x61, 0x67, 0x65, 0x2e, 0x66, 0x72, 0x61, 0x6d, 0x65, 0x44, 0x65, 0x74, 0x61, 0x63, 0x68,
+    0x65, 0x64, 0x66, 0x70, 0x61, 0x72, 0x61, 0x6d, 0x73, 0xd8, 0x18, 0x5a, 0x00, 0x00, 0x00, 0x1a,
+    0xbf, 0x67, 0x66, 0x72, 0x61, 0x6d, 0x65, 0x49, 0x64, 0x61, 0x31, 0x66, 0x72, 0x65, 0x61, 0x73,
+    0x6f, 0x6e, 0x66, 0x72, 0x65, 0x6d, 0x6f, 0x76, 0x65, 0xff, 0xff,
+];
+
+/// `{"data":"AAEC"}` of `Page.captureScreenshot`, with the data as binary.
+const SCREENSHOT: &[u8] = &[
+    0xd8, 0x18, 0x5a, 0x00, 0x00, 0x00, 0x0c, 0xbf, 0x64, 0x64, 0x61, 0x74, 0x61, 0xd6, 0x43, 0x00,
+    0x01, 0x02, 0xff,
+];
+
+/// `{"x":0.5,"y":0,"width":800,"height":600,"scale":1.5}`
+const VIEWPORT: &[u8] = &[
+    0xd8, 0x18, 0x5a, 0x00, 0x00, 0x00, 0x32, 0xbf, 0x61, 0x78, 0xfb, 0x3f, 0xe0, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x61, 0x79, 0x00, 0x65, 0x77, 0x69, 0x64, 0x74, 0x68, 0x19, 0x03, 0x20, 0x66,
+    0x68, 0x65, 0x69, 0x67, 0x68, 0x74, 0x19, 0x02, 0x58, 0x65, 0x73, 0x63, 0x61, 0x6c, 0x65, 0xfb,
+    0x3f, 0xf8, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff,
+];
+
+#[derive(Deserialize)]
+struct Response<T> {
+    id: u32,
+    result: T,
+}
+
+#[test]
+fn encodes_method_calls() {
+    let navigate = Page::Navigate {
+        url: "https://example.com".to_string(),
+        referrer: None,
+        transition_type: None,
+        frame_id: None,
+        referrer_policy: None,
+    };
+
+    assert_eq!(cbor::to_vec(&navigate.to_method_call(1)).unwrap(), NAVIGATE);
+}
+
+#[test]
+fn decodes_responses() {
+    let response: Response<<Page::Navigate as Method>::ReturnObject> =
+        cbor::from_slice(NAVIGATE_RESPONSE).unwrap();
+
+    assert_eq!(response.id, 1);
+    assert_eq!(response.result.frame_id, "F");
+    assert_eq!(response.result.loader_id.as_deref(), Some("L"));
+}
+
+#[test]
+fn decodes_events() {
+    let event: Event = cbor::from_slice(FRAME_DETACHED).unwrap();
+
+    match event {
+        Event::PageFrameDetached(event) => assert_eq!(event.params.frame_id, "1"),
+        event => panic!("unexpected {:?}", event),
+    }
+}
+
+#[test]
+fn encodes_binary_data_as_bytes() {
+    let screenshot: Page::CaptureScreenshotReturnObject = cbor::from_slice(SCREENSHOT).unwrap();
+
+    assert_eq!(screenshot.data, "AAEC");
+    assert_eq!(cbor::to_vec(&screenshot).unwrap(), SCREENSHOT);
+    assert_eq!(
+        serde_json::to_string(&screenshot).unwrap(),
+        r#"{"data":"AAEC"}"#
+    );
+
+    for data in &["", "AA==", "AAE=", "AAECAw==", "not base64"] {
+        let screenshot = Page::CaptureScreenshotReturnObject {
+            data: data.to_string(),
+        };
+        let bytes = cbor::to_vec(&screenshot).unwrap();
+        assert_eq!(
+            cbor::from_slice::<Page::CaptureScreenshotReturnObject>(&bytes).unwrap(),
+            screenshot
+        );
+    }
+}
+
+#[test]
+fn reads_integers_as_numbers() {
+    let viewport: Page::Viewport = cbor::from_slice(VIEWPORT).unwrap();
+
+    assert_eq!((viewport.x, viewport.y), (0.5, 0.0));
+    assert_eq!((viewport.width, viewport.height), (800.0, 600.0));
+    assert_eq!(
+        cbor::from_slice::<Page::Viewport>(&cbor::to_vec(&viewport).unwrap()).unwrap(),
+        viewport
+    );
+}
+
+#[test]
+fn reads_utf16_strings() {
+    let title = [0x48, 0x63, 0x00, 0x61, 0x00, 0x66, 0x00, 0xe9, 0x00];
+
+    assert_eq!(cbor::from_slice::<String>(&title).unwrap(), "café");
+}
+
+#[test]
+fn rejects_truncated_envelopes() {
+    assert!(cbor::from_slice::<Event>(&FRAME_DETACHED[..FRAME_DETACHED.len() - 1]).is_err());
+}
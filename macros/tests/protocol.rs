@@ -0,0 +1,274 @@
+auto_generate_cdp_macros::protocol! {
+    json = ["../json/js_protocol.json", "../json/browser_protocol.json"],
+    domains = [Page],
+    raw_events = true,
+}
+
+use cdp::types::{Event, Method, RawEvent};
+use cdp::Runtime::{CallArgument, RemoteObject};
+
+#[test]
+fn generates_selected_domains() {
+    assert_eq!(cdp::Page::Navigate::NAME, "Page.navigate");
+    assert_eq!(cdp::Runtime::Evaluate::NAME, "Runtime.evaluate");
+
+    let event: Event = serde_json::from_str(
+        r#"{ "method": "Page.frameDetached", "params": { "frameId": "1", "reason": "remove" } }"#,
+    )
+    .unwrap();
+    assert!(matches!(event, Event::PageFrameDetached(_)));
+
+    // missing required fields, here the `reason` enum, are errors by default
+    assert!(serde_json::from_str::<Event>(
+        r#"{ "method": "Page.frameDetached", "params": { "frameId": "1" } }"#,
+    )
+    .is_err());
+}
+
+/// The `.json` and `.pdl` files of a directory.
+mod custom {
+    auto_generate_cdp_macros::protocol! {
+        json = "tests/custom/",
+    }
+}
+
+#[test]
+fn reads_directories() {
+    use custom::cdp::{types::Method as _, Custom, Other};
+
+    assert_eq!(Custom::Capture::NAME, "Custom.capture");
+    assert_eq!(Other::Ping::NAME, "Other.ping");
+
+    let capture: Custom::CaptureReturnObject =
+        serde_json::from_str(r#"{ "data": "AAEC" }"#).unwrap();
+    assert_eq!(capture.data, "AAEC");
+}
+
+#[test]
+fn describes_methods() {
+    fn flags<M: Method>() -> (&'static str, bool, bool) {
+        (M::DOMAIN, M::IS_EXPERIMENTAL, M::IS_DEPRECATED)
+    }
+    assert_eq!(flags::<cdp::Page::Navigate>(), ("Page", false, false));
+    assert_eq!(flags::<cdp::Page::CaptureSnapshot>(), ("Page", true, false));
+    assert_eq!(
+        flags::<cdp::Page::ClearGeolocationOverride>(),
+        ("Page", false, true)
+    );
+
+    // hand-written impls keep compiling without the flags
+    #[derive(Debug)]
+    struct Custom;
+    impl Method for Custom {
+        const NAME: &'static str = "Custom.method";
+        type ReturnObject = serde_json::Value;
+    }
+    assert_eq!(flags::<Custom>(), ("", false, false));
+
+    let navigate = cdp::METHODS
+        .iter()
+        .find(|method| method.name == "Page.navigate")
+        .unwrap();
+    assert_eq!(navigate.domain, "Page");
+    assert!(navigate.parameters.contains(&"frameId"));
+    assert!(cdp::METHODS
+        .iter()
+        .any(|method| method.name == "Runtime.evaluate"));
+    assert!(!cdp::METHODS
+        .iter()
+        .any(|method| method.domain == "Animation"));
+}
+
+#[test]
+fn reads_null_free_form_objects() {
+    let headers: cdp::Network::Headers = serde_json::from_str("null").unwrap();
+    assert!(headers.0.is_empty());
+
+    let event: Event = serde_json::from_str(
+        r#"{ "method": "Runtime.inspectRequested", "params": { "object": { "type": "object" }, "hints": null } }"#,
+    )
+    .unwrap();
+    match event {
+        Event::RuntimeInspectRequested(event) => assert!(event.params.hints.is_empty()),
+        event => panic!("unexpected {:?}", event),
+    }
+}
+
+#[test]
+fn looks_up_headers_ignoring_case() {
+    let mut headers: cdp::Network::Headers =
+        serde_json::from_str(r#"{ "Content-Type": "text/html" }"#).unwrap();
+    assert_eq!(headers.get("content-type"), Some("text/html"));
+    assert_eq!(headers.get("CONTENT-TYPE"), Some("text/html"));
+    assert!(headers.contains("Content-type"));
+    assert_eq!(headers.get("content-length"), None);
+
+    // inserting replaces the header in any case instead of adding another
+    assert_eq!(
+        headers.insert("content-type", "text/plain"),
+        Some(String::from("text/html"))
+    );
+    assert_eq!(headers.0.len(), 1);
+    assert_eq!(headers.get("Content-Type"), Some("text/plain"));
+    assert_eq!(headers.insert("Accept", "*/*"), None);
+    assert_eq!(headers.0.len(), 2);
+
+    assert_eq!(
+        headers.remove("CONTENT-TYPE"),
+        Some(String::from("text/plain"))
+    );
+    assert_eq!(headers.remove("content-type"), None);
+    assert_eq!(
+        serde_json::to_value(&headers).unwrap(),
+        serde_json::json!({ "Accept": "*/*" })
+    );
+}
+
+#[test]
+fn picks_map_forms() {
+    use std::collections::{BTreeMap, HashMap};
+
+    // headers are `HashMap`s of strings, the other free-form objects `BTreeMap`s of JSON
+    let headers: cdp::Network::Headers = serde_json::from_str("{}").unwrap();
+    let _: &HashMap<String, String> = &headers.0;
+
+    let event: Event = serde_json::from_str(
+        r#"{ "method": "Runtime.inspectRequested",
+             "params": { "object": { "type": "object" }, "hints": { "queryObjects": true, "copyToClipboard": 1 } } }"#,
+    )
+    .unwrap();
+    let hints = match event {
+        Event::RuntimeInspectRequested(event) => event.params.hints,
+        event => panic!("unexpected {:?}", event),
+    };
+    let _: &BTreeMap<String, serde_json::Value> = &hints;
+    assert_eq!(
+        hints.keys().collect::<Vec<_>>(),
+        ["copyToClipboard", "queryObjects"]
+    );
+    assert_eq!(hints["queryObjects"], serde_json::json!(true));
+}
+
+#[test]
+fn wraps_redirected_commands() {
+    use cdp::{Emulation, Network};
+
+    // `Network.setUserAgentOverride` has moved to `Emulation`
+    let params = Emulation::SetUserAgentOverride {
+        user_agent: String::from("agent"),
+        accept_language: None,
+        platform: None,
+        user_agent_metadata: None,
+    };
+    let redirected = Network::SetUserAgentOverride::from(params.clone());
+    assert_eq!(redirected.0, params);
+    assert_eq!(
+        serde_json::to_value(&redirected).unwrap(),
+        serde_json::json!({ "userAgent": "agent" })
+    );
+    assert_eq!(
+        serde_json::from_value::<Network::SetUserAgentOverride>(
+            serde_json::json!({ "userAgent": "agent" })
+        )
+        .unwrap(),
+        redirected
+    );
+
+    assert_eq!(
+        Network::SetUserAgentOverride::NAME,
+        "Network.setUserAgentOverride"
+    );
+    assert_eq!(
+        serde_json::to_value(redirected.to_method_call(1)).unwrap(),
+        serde_json::json!({
+            "method": "Network.setUserAgentOverride",
+            "id": 1,
+            "params": { "userAgent": "agent" },
+        })
+    );
+
+    let returns: <Network::SetUserAgentOverride as Method>::ReturnObject =
+        Emulation::SetUserAgentOverrideReturnObject {};
+    let _: Network::SetUserAgentOverrideReturnObject = returns;
+
+    // the metadata lists what the wrapper serializes, which is the target's parameters
+    let info = |name| {
+        cdp::METHODS
+            .iter()
+            .find(|method| method.name == name)
+            .unwrap()
+    };
+    let network = info("Network.setUserAgentOverride");
+    assert_eq!(network.domain, "Network");
+    assert_eq!(
+        network.parameters,
+        info("Emulation.setUserAgentOverride").parameters
+    );
+}
+
+#[test]
+fn decodes_raw_events_lazily() {
+    let event: RawEvent = serde_json::from_str(
+        r#"{ "method": "Page.frameDetached", "sessionId": "A", "params": { "frameId": "1", "reason": "remove" } }"#,
+    )
+    .unwrap();
+    assert_eq!(event.kind(), "Page.frameDetached");
+    assert_eq!(event.session_id.as_deref(), Some("A"));
+
+    let detached = event
+        .decode::<cdp::Page::events::FrameDetachedEvent>()
+        .unwrap();
+    assert_eq!(detached.params.frame_id, "1");
+    assert!(event
+        .decode::<cdp::Page::events::FrameAttachedEvent>()
+        .is_err());
+}
+
+#[test]
+fn converts_remote_values() {
+    let object = |json: &str| -> RemoteObject { serde_json::from_str(json).unwrap() };
+
+    let number = object(r#"{ "type": "number", "value": 1.5, "description": "1.5" }"#);
+    assert_eq!(number.to_value::<f64>().unwrap(), 1.5);
+    let nan = object(r#"{ "type": "number", "unserializableValue": "NaN" }"#);
+    assert!(nan.to_value::<f64>().unwrap().is_nan());
+    let zero = object(r#"{ "type": "number", "unserializableValue": "-0" }"#);
+    assert!(zero.to_value::<f64>().unwrap().is_sign_negative());
+    let bigint = object(
+        r#"{ "type": "bigint", "unserializableValue": "-170141183460469231731687303715884105728n" }"#,
+    );
+    assert_eq!(bigint.to_value::<i128>().unwrap(), i128::MIN);
+    let string = object(r#"{ "type": "string", "value": "a" }"#);
+    assert_eq!(string.to_value::<&str>().unwrap(), "a");
+    let undefined = object(r#"{ "type": "undefined" }"#);
+    assert_eq!(undefined.to_value::<Option<bool>>().unwrap(), None);
+
+    let argument = CallArgument::from_value(&f64::NEG_INFINITY).unwrap();
+    assert_eq!(argument.unserializable_value.as_deref(), Some("-Infinity"));
+    let argument = CallArgument::from_value(&(u128::MAX)).unwrap();
+    assert_eq!(
+        argument.unserializable_value.as_deref(),
+        Some("340282366920938463463374607431768211455n")
+    );
+    let argument = CallArgument::from_value(&[1.0, 2.5]).unwrap();
+    assert_eq!(argument.value, Some(serde_json::json!([1.0, 2.5])));
+    let argument = CallArgument::from_value(&(1, "a", [Some(true), None])).unwrap();
+    assert_eq!(
+        argument.value,
+        Some(serde_json::json!([1, "a", [true, null]]))
+    );
+    let nested = |result: serde_json::Result<CallArgument>| {
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "unrepresentable number in compound value"
+        );
+    };
+    nested(CallArgument::from_value(&vec![f64::NAN]));
+    nested(CallArgument::from_value(&[1.0, -0.0]));
+    nested(CallArgument::from_value(&(1, [f64::INFINITY])));
+    nested(CallArgument::from_value(
+        &std::iter::once(("big", i128::MAX)).collect::<std::collections::BTreeMap<_, _>>(),
+    ));
+    let argument = CallArgument::from_remote_object(&bigint);
+    assert_eq!(argument.unserializable_value, bigint.unserializable_value);
+}
@@ -0,0 +1,130 @@
+auto_generate_cdp_macros::protocol! {
+    json = ["../json/js_protocol.json", "../json/browser_protocol.json"],
+    domains = [Input, Network],
+    time_crates = [chrono, time],
+}
+
+use std::time::{Duration, UNIX_EPOCH};
+
+use cdp::{Input, Network, Runtime};
+
+#[test]
+fn converts_seconds_since_epoch() {
+    let time = Network::TimeSinceEpoch(1.5);
+    assert_eq!(
+        time.to_system_time(),
+        Some(UNIX_EPOCH + Duration::from_millis(1500))
+    );
+    assert_eq!(
+        Network::TimeSinceEpoch::from(UNIX_EPOCH + Duration::from_millis(1500)),
+        time
+    );
+    assert_eq!(
+        Input::TimeSinceEpoch(-1.0).to_system_time(),
+        UNIX_EPOCH.checked_sub(Duration::from_secs(1))
+    );
+    assert_eq!(Network::TimeSinceEpoch(f64::NAN).to_system_time(), None);
+}
+
+#[test]
+fn converts_durations() {
+    assert_eq!(
+        Network::MonotonicTime(0.25).to_duration(),
+        Some(Duration::from_millis(250))
+    );
+    assert_eq!(
+        Network::MonotonicTime::from(Duration::from_millis(250)),
+        Network::MonotonicTime(0.25)
+    );
+    assert_eq!(Network::MonotonicTime(-1.0).to_duration(), None);
+    assert_eq!(Network::MonotonicTime(f64::INFINITY).to_duration(), None);
+}
+
+#[test]
+fn counts_milliseconds_or_seconds() {
+    // `Runtime.Timestamp` and `Runtime.TimeDelta` count milliseconds
+    assert_eq!(
+        Runtime::Timestamp(1500.0).to_system_time(),
+        Network::TimeSinceEpoch(1.5).to_system_time()
+    );
+    assert_eq!(
+        Runtime::Timestamp::from(UNIX_EPOCH + Duration::from_secs(2)),
+        Runtime::Timestamp(2000.0)
+    );
+    assert_eq!(
+        Runtime::TimeDelta(250.0).to_duration(),
+        Network::MonotonicTime(0.25).to_duration()
+    );
+    assert_eq!(
+        Runtime::TimeDelta::from(Duration::from_secs(1)),
+        Runtime::TimeDelta(1000.0)
+    );
+}
+
+#[test]
+fn round_trips_json() {
+    let time: Network::TimeSinceEpoch = serde_json::from_str("1.5").unwrap();
+    assert_eq!(time, Network::TimeSinceEpoch(1.5));
+    assert_eq!(serde_json::to_string(&time).unwrap(), "1.5");
+
+    let delta: Runtime::TimeDelta = serde_json::from_str("250").unwrap();
+    assert_eq!(delta.to_duration(), Some(Duration::from_millis(250)));
+    assert_eq!(serde_json::to_string(&delta).unwrap(), "250.0");
+}
+
+#[test]
+fn round_trips_chrono() {
+    use chrono::TimeZone;
+
+    let expected = chrono::Utc.timestamp_opt(1, 500_000_000).unwrap();
+    assert_eq!(Network::TimeSinceEpoch(1.5).to_chrono(), Some(expected));
+    assert_eq!(Runtime::Timestamp(1500.0).to_chrono(), Some(expected));
+    assert_eq!(
+        Network::TimeSinceEpoch::from(expected),
+        Network::TimeSinceEpoch(1.5)
+    );
+    assert_eq!(
+        Runtime::Timestamp::from(expected),
+        Runtime::Timestamp(1500.0)
+    );
+    assert_eq!(Network::TimeSinceEpoch(f64::NAN).to_chrono(), None);
+
+    assert_eq!(
+        Network::MonotonicTime(0.25).to_chrono_duration(),
+        Some(chrono::Duration::milliseconds(250))
+    );
+    assert_eq!(
+        Runtime::TimeDelta(250.0).to_chrono_duration(),
+        Some(chrono::Duration::milliseconds(250))
+    );
+}
+
+#[test]
+fn round_trips_time() {
+    let expected = time::OffsetDateTime::from_unix_timestamp_nanos(1_500_000_000).unwrap();
+    assert_eq!(
+        Network::TimeSinceEpoch(1.5).to_offset_date_time(),
+        Some(expected)
+    );
+    assert_eq!(
+        Runtime::Timestamp(1500.0).to_offset_date_time(),
+        Some(expected)
+    );
+    assert_eq!(
+        Network::TimeSinceEpoch::from(expected),
+        Network::TimeSinceEpoch(1.5)
+    );
+    assert_eq!(
+        Runtime::Timestamp::from(expected),
+        Runtime::Timestamp(1500.0)
+    );
+
+    assert_eq!(
+        Network::MonotonicTime(0.25).to_time_duration(),
+        Some(time::Duration::milliseconds(250))
+    );
+    assert_eq!(
+        Runtime::TimeDelta(250.0).to_time_duration(),
+        Some(time::Duration::milliseconds(250))
+    );
+}
@@ -0,0 +1,633 @@
+//! Derives and attributes added to the generated structs and enums on top of the
+//! ones every type has, as configured with [`Options::derive`] and
+//! [`Options::attribute`].
+
+use std::cell::RefCell;
+use std::collections::{BTreeSet, HashMap};
+
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::quote;
+
+use crate::codegen::Options;
+use crate::free_form::FreeFormObject;
+use crate::symbols::SymbolTable;
+use crate::types::{Items, Parameter, Protocol, TypeElement, TypeEnum};
+
+/// Derives every generated struct and enum has.
+const BASE_DERIVES: [&str; 5] = ["Deserialize", "Serialize", "Debug", "Clone", "PartialEq"];
+
+/// Derives every enum of strings has on top of the base ones, as its variants hold
+/// nothing.
+const ENUM_DERIVES: [&str; 5] = ["Eq", "Hash", "PartialOrd", "Ord", "Copy"];
+
+/// Derives whose requirements on fields are known, so that they are left out of
+/// the types that cannot have them.
+const CHECKED_DERIVES: [&str; 7] = [
+    "PartialEq",
+    "Eq",
+    "Hash",
+    "PartialOrd",
+    "Ord",
+    "Default",
+    "Copy",
+];
+
+/// The derives and attributes of the generated items, which are named by their
+/// domain and protocol name: the type id, or the command or event name for their
+/// parameters, return object and inline enums.
+pub struct Attributes<'a> {
+    options: &'a Options,
+    protocols: &'a [Protocol],
+    symbols: &'a SymbolTable<'a>,
+    /// The checked derives each protocol type has, keyed by `Domain.Type`.
+    derivable: HashMap<String, BTreeSet<&'static str>>,
+    /// The checked derives requested for each protocol type or the items holding
+    /// it, which cannot have them unless it has them too, keyed by `Domain.Type`.
+    requests: HashMap<String, BTreeSet<&'static str>>,
+    /// Derives requested for a single item that it cannot have, with its domain.
+    dropped: RefCell<Vec<(String, String)>>,
+}
+
+impl<'a> Attributes<'a> {
+    pub fn new(
+        protocols: &'a [Protocol],
+        symbols: &'a SymbolTable<'a>,
+        options: &'a Options,
+    ) -> Self {
+        let mut attributes = Attributes {
+            options,
+            protocols,
+            symbols,
+            derivable: HashMap::new(),
+            requests: HashMap::new(),
+            dropped: RefCell::new(Vec::new()),
+        };
+
+        // a derive requested for an item is requested for the types it holds too
+        let checked = |domain, name| {
+            matching(&options.derives, domain, name).filter_map(|derive| {
+                CHECKED_DERIVES
+                    .iter()
+                    .copied()
+                    .find(|checked| *checked == last_segment(derive))
+            })
+        };
+        let mut pending: Vec<(&str, &str, &'static str)> = Vec::new();
+        for dom in protocols.iter().flat_map(|protocol| &protocol.domains) {
+            let domain = dom.domain.as_str();
+            for element in dom.types.iter().flatten() {
+                for derive in checked(domain, &element.id) {
+                    pending.push((domain, &element.id, derive));
+                }
+            }
+            let commands = dom.commands.iter().map(|command| {
+                let fields = command.parameters.iter().chain(&command.returns);
+                (&command.name, fields.flatten().collect::<Vec<_>>())
+            });
+            let events = dom
+                .events
+                .iter()
+                .flatten()
+                .map(|event| (&event.name, event.parameters.iter().flatten().collect()));
+            for (name, fields) in commands.chain(events) {
+                for derive in checked(domain, name) {
+                    for reference in fields.iter().filter_map(|field| held_type(field)) {
+                        pending.push((domain, reference, derive));
+                    }
+                }
+            }
+        }
+        while let Some((domain, reference, derive)) = pending.pop() {
+            let symbol = symbols.resolve(domain, reference).unwrap();
+            let key = format!("{}.{}", symbol.domain, symbol.element.id);
+            if attributes.requests.entry(key).or_default().insert(derive) {
+                for reference in held_types(symbol.element) {
+                    pending.push((symbol.domain, reference, derive));
+                }
+            }
+        }
+
+        let (overridden, elements): (Vec<(&str, &TypeElement)>, _) = protocols
+            .iter()
+            .flat_map(|protocol| &protocol.domains)
+            .flat_map(|dom| {
+                let domain = dom.domain.as_str();
+                dom.types
+                    .iter()
+                    .flatten()
+                    .map(move |element| (domain, element))
+            })
+            .partition(|(domain, element)| symbols.is_overridden(domain, &element.id));
+
+        // nothing is known of the types replacing protocol types but the base derives
+        for (domain, element) in overridden {
+            attributes.derivable.insert(
+                format!("{}.{}", domain, element.id),
+                BASE_DERIVES
+                    .iter()
+                    .copied()
+                    .filter(|derive| CHECKED_DERIVES.contains(derive))
+                    .collect(),
+            );
+        }
+
+        // every type starts out with every derive and loses the ones its fields
+        // don't have until nothing changes, so that recursive types keep theirs
+        for (domain, element) in &elements {
+            attributes.derivable.insert(
+                format!("{}.{}", domain, element.id),
+                CHECKED_DERIVES.iter().copied().collect(),
+            );
+        }
+
+        loop {
+            let updates: Vec<(String, BTreeSet<&'static str>)> = elements
+                .iter()
+                .filter_map(|(domain, element)| {
+                    let key = format!("{}.{}", domain, element.id);
+                    let derives: BTreeSet<&'static str> = attributes.derivable[&key]
+                        .iter()
+                        .copied()
+                        .filter(|derive| attributes.type_has(domain, element, derive))
+                        .collect();
+
+                    if derives != attributes.derivable[&key] {
+                        Some((key, derives))
+                    } else {
+                        None
+                    }
+                })
+                .collect();
+
+            if updates.is_empty() {
+                break;
+            }
+            attributes.derivable.extend(updates);
+        }
+
+        attributes
+    }
+
+    /// The attributes of a struct with `fields`.
+    pub fn of_fields(&self, domain: &str, name: &str, fields: &[Parameter]) -> TokenStream {
+        self.render(domain, name, &[], |derive| {
+            fields
+                .iter()
+                .all(|field| self.field_has(domain, name, field, derive))
+        })
+    }
+
+    /// The attributes of a struct wrapping an optional `serde_json::Value`.
+    pub fn of_json(&self, domain: &str, name: &str) -> TokenStream {
+        self.render(domain, name, &[], |derive| {
+            derive == "Default" || self.primitive_has(TypeEnum::Any, derive)
+        })
+    }
+
+    /// The attributes of the free-form object type `id`.
+    pub fn of_object(&self, domain: &str, id: &str, object: &FreeFormObject) -> TokenStream {
+        self.render(domain, id, &[], |derive| self.object_has(object, derive))
+    }
+
+    /// The attributes of a newtype around the protocol type `id`.
+    pub fn of_type(&self, domain: &str, id: &str) -> TokenStream {
+        let derivable = &self.derivable[&format!("{}.{}", domain, id)];
+        self.render(domain, id, &[], |derive| derivable.contains(derive))
+    }
+
+    /// The attributes of an enum of strings, which has every checked derive but
+    /// `Default`, and that only in lenient mode.
+    pub fn of_enum(&self, domain: &str, name: &str) -> TokenStream {
+        self.render(domain, name, &ENUM_DERIVES, |derive| {
+            derive != "Default" || self.options.lenient()
+        })
+    }
+
+    /// Whether the required `field` of the item `name` falls back to its default
+    /// value when missing.
+    pub fn has_default(&self, domain: &str, name: &str, field: &Parameter) -> bool {
+        self.options.lenient() && self.field_has(domain, name, field, "Default")
+    }
+
+    /// The attributes of a wrapper around the parameters of the same-named command
+    /// in `target_domain`.
+    pub fn of_redirect(&self, domain: &str, name: &str, target_domain: &str) -> TokenStream {
+        let parameters = self
+            .protocols
+            .iter()
+            .find_map(|protocol| protocol.domain(target_domain)?.find_command(name))
+            .and_then(|command| command.parameters.as_deref());
+
+        self.render(domain, name, &[], |derive| match parameters {
+            Some(parameters) => parameters
+                .iter()
+                .all(|field| self.field_has(target_domain, name, field, derive)),
+            None => derive == "Default" || self.primitive_has(TypeEnum::Any, derive),
+        })
+    }
+
+    /// Derives requested for a single item of `domain` that it cannot have, as
+    /// warnings.
+    pub fn dropped(&self, domain: &str) -> Vec<String> {
+        self.dropped
+            .borrow()
+            .iter()
+            .filter(|(dropped_domain, _)| dropped_domain == domain)
+            .map(|(_, warning)| warning.clone())
+            .collect()
+    }
+
+    /// `#[derive]` with the base derives, the `implied` ones and the configured ones
+    /// that `possible` allows, `Default` too in lenient mode, followed by the
+    /// configured attributes.
+    fn render<F>(&self, domain: &str, name: &str, implied: &[&str], possible: F) -> TokenStream
+    where
+        F: Fn(&str) -> bool,
+    {
+        let configured = || matching(&self.options.derives, domain, name).map(String::as_str);
+        let lenient = Some("Default").filter(|_| {
+            self.options.lenient() && configured().all(|derive| last_segment(derive) != "Default")
+        });
+
+        let mut seen: Vec<&str> = BASE_DERIVES.iter().chain(implied).copied().collect();
+        let mut derives: Vec<TokenStream> = BASE_DERIVES
+            .iter()
+            .filter(|derive| !CHECKED_DERIVES.contains(derive) || possible(derive))
+            .chain(implied)
+            .map(|derive| {
+                let derive = Ident::new(derive, Span::call_site());
+                quote! { #derive }
+            })
+            .collect();
+
+        for derive in configured().chain(lenient) {
+            let checked = last_segment(derive);
+            if seen.contains(&derive) || seen.contains(&checked) {
+                continue;
+            }
+            if CHECKED_DERIVES.contains(&checked) && !possible(checked) {
+                // only a request naming the item itself says that it should have it
+                let path = format!("{}.{}", domain, name);
+                let single = self
+                    .options
+                    .derives
+                    .iter()
+                    .any(|(requested_path, requested)| {
+                        *requested_path == path && requested == derive
+                    });
+                if single {
+                    self.dropped.borrow_mut().push((
+                        domain.to_string(),
+                        format!(
+                            "`{}` does not derive `{}`, which a type it holds lacks",
+                            path, derive
+                        ),
+                    ));
+                }
+                continue;
+            }
+            seen.push(derive);
+            derives.push(
+                derive
+                    .parse()
+                    .unwrap_or_else(|_| panic!("invalid derive `{}`", derive)),
+            );
+        }
+
+        let attributes =
+            matching(&self.options.attributes, domain, name).map(|attribute| -> TokenStream {
+                attribute
+                    .parse()
+                    .unwrap_or_else(|_| panic!("invalid attribute `{}`", attribute))
+            });
+
+        quote! {
+            #[derive(#(#derives),*)]
+            #(#attributes)*
+        }
+    }
+
+    fn requested(&self, domain: &str, name: &str, derive: &str) -> bool {
+        BASE_DERIVES.contains(&derive)
+            || (derive == "Default" && self.options.lenient())
+            || matching(&self.options.derives, domain, name)
+                .any(|requested| last_segment(requested) == derive)
+            || self
+                .requests
+                .get(&format!("{}.{}", domain, name))
+                .is_some_and(|requests| requests.contains(derive))
+    }
+
+    /// Whether the protocol type `element` can have `derive`, given what the types it
+    /// refers to have so far.
+    fn type_has(&self, domain: &str, element: &TypeElement, derive: &str) -> bool {
+        match element.type_type {
+            TypeEnum::Array => {
+                derive == "Default"
+                    || (derive != "Copy"
+                        && self.items_have(domain, &element.id, element.items.as_ref(), derive))
+            }
+            TypeEnum::Object => {
+                let properties = element.properties.as_deref().unwrap_or_default();
+
+                self.requested(domain, &element.id, derive)
+                    && if properties.iter().any(is_field) {
+                        properties
+                            .iter()
+                            .all(|field| self.field_has(domain, &element.id, field, derive))
+                    } else {
+                        let object = FreeFormObject::of_type(self.options, domain, &element.id);
+                        self.object_has(&object, derive)
+                    }
+            }
+            TypeEnum::String if element.type_enum.is_some() => {
+                ENUM_DERIVES.contains(&derive)
+                    || ((derive != "Default" || self.options.lenient())
+                        && self.requested(domain, &element.id, derive))
+            }
+            type_type => self.primitive_has(type_type, derive),
+        }
+    }
+
+    /// Whether the field of the item `name` can have `derive`.
+    fn field_has(&self, domain: &str, name: &str, field: &Parameter, derive: &str) -> bool {
+        if field.optional.unwrap_or(false) && derive == "Default" {
+            return true;
+        }
+
+        if let Some(reference) = &field.parameter_ref {
+            let symbol = self.symbols.resolve(domain, reference).unwrap();
+
+            // boxed fields close a cycle, which defaulting would go around forever
+            if self.symbols.is_boxed(domain, name, &field.name)
+                && matches!(derive, "Copy" | "Default")
+            {
+                return false;
+            }
+            return self.derivable[&format!("{}.{}", symbol.domain, symbol.element.id)]
+                .contains(derive);
+        }
+
+        match field.parameter_type {
+            Some(TypeEnum::Array) => {
+                derive == "Default"
+                    || (derive != "Copy"
+                        && self.items_have(domain, name, field.items.as_ref(), derive))
+            }
+            Some(TypeEnum::String) if field.parameter_enum.is_some() => {
+                // shared enums have the derives of the item declaring them first
+                let owner = self
+                    .symbols
+                    .inline_enum(domain, name, field)
+                    .map_or(name, |inline_enum| inline_enum.owner);
+                ENUM_DERIVES.contains(&derive)
+                    || ((derive != "Default" || self.options.lenient())
+                        && self.requested(domain, owner, derive))
+            }
+            Some(TypeEnum::Object) => {
+                self.object_has(&FreeFormObject::inline(self.options, domain, name), derive)
+            }
+            Some(type_type) => self.primitive_has(type_type, derive),
+            None => true,
+        }
+    }
+
+    /// Whether the items of an array of the item `name` have `derive`.
+    fn items_have(&self, domain: &str, name: &str, items: Option<&Items>, derive: &str) -> bool {
+        match items {
+            Some(Items {
+                items_ref: Some(reference),
+                ..
+            }) => {
+                let symbol = self.symbols.resolve(domain, reference).unwrap();
+                self.derivable[&format!("{}.{}", symbol.domain, symbol.element.id)].contains(derive)
+            }
+            Some(Items {
+                items_type: Some(TypeEnum::Object),
+                ..
+            }) => self.object_has(&FreeFormObject::inline(self.options, domain, name), derive),
+            Some(Items {
+                items_type: Some(items_type),
+                ..
+            }) => self.primitive_has(*items_type, derive),
+            _ => true,
+        }
+    }
+
+    /// Whether the free-form `object` has `derive`.
+    fn object_has(&self, object: &FreeFormObject, derive: &str) -> bool {
+        match object.values() {
+            None => derive == "Default" || self.primitive_has(TypeEnum::Any, derive),
+            Some(values) => match derive {
+                "Default" => true,
+                "Copy" => false,
+                "Hash" | "PartialOrd" | "Ord" if object.hashed() => false,
+                derive => self.primitive_has(values, derive),
+            },
+        }
+    }
+
+    /// Whether the Rust type of a JSON type has `derive`. Arrays of arrays have no
+    /// Rust type and are left out of the generated structs.
+    fn primitive_has(&self, type_type: TypeEnum, derive: &str) -> bool {
+        match type_type {
+            TypeEnum::Number => !matches!(derive, "Eq" | "Hash" | "Ord"),
+            TypeEnum::String => derive != "Copy",
+            // `&RawValue` only has `Copy`
+            TypeEnum::Any if self.options.borrowed => derive == "Copy",
+            // `serde_json::Value` is only `Hash` in recent versions
+            TypeEnum::Any => !matches!(derive, "Hash" | "PartialOrd" | "Ord" | "Copy"),
+            TypeEnum::Boolean | TypeEnum::Integer | TypeEnum::Array | TypeEnum::Object => true,
+        }
+    }
+}
+
+/// The protocol type `field` refers to, directly or as the items of an array.
+fn held_type(field: &Parameter) -> Option<&str> {
+    field
+        .parameter_ref
+        .as_deref()
+        .or_else(|| field.items.as_ref()?.items_ref.as_deref())
+}
+
+/// The protocol types the type `element` refers to.
+fn held_types(element: &TypeElement) -> impl Iterator<Item = &str> {
+    let items = element
+        .items
+        .as_ref()
+        .and_then(|items| items.items_ref.as_deref());
+    element
+        .properties
+        .iter()
+        .flatten()
+        .filter_map(held_type)
+        .chain(items)
+}
+
+/// Whether `parameter` is generated as a field, see `compile::field_type`.
+pub fn is_field(parameter: &Parameter) -> bool {
+    if parameter.parameter_ref.is_some() {
+        return true;
+    }
+
+    match parameter.parameter_type {
+        Some(TypeEnum::Array) => parameter.items.as_ref().is_some_and(|items| {
+            items.items_ref.is_some() || !matches!(items.items_type, Some(TypeEnum::Array))
+        }),
+        Some(_) => true,
+        None => false,
+    }
+}
+
+/// The configured values whose path matches the item: `*`, its domain or
+/// `Domain.name`.
+pub fn matching<'b, T>(
+    entries: &'b [(String, T)],
+    domain: &'b str,
+    name: &'b str,
+) -> impl Iterator<Item = &'b T> {
+    entries
+        .iter()
+        .filter(move |(path, _)| match path.split_once('.') {
+            Some((path_domain, path_name)) => path_domain == domain && path_name == name,
+            None => path == "*" || path == domain,
+        })
+        .map(|(_, value)| value)
+}
+
+/// `Hash` of `std::hash::Hash`.
+fn last_segment(path: &str) -> &str {
+    path.rsplit("::").next().unwrap_or(path).trim()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codegen::MissingFields;
+
+    const DOM: &str = r#"{
+        "version": { "major": "1", "minor": "3" },
+        "domains": [{
+            "domain": "DOM",
+            "types": [
+                { "id": "Rect", "type": "object", "properties": [
+                    { "name": "width", "type": "number" }
+                ] },
+                { "id": "Node", "type": "object", "properties": [
+                    { "name": "name", "type": "string" },
+                    { "name": "children", "type": "array", "items": { "$ref": "Node" } },
+                    { "name": "parent", "$ref": "Node", "optional": true }
+                ] },
+                { "id": "Box", "type": "object", "properties": [
+                    { "name": "content", "$ref": "Rect" },
+                    { "name": "kind", "type": "string", "enum": ["a", "b"] }
+                ] }
+            ],
+            "commands": []
+        }]
+    }"#;
+
+    #[test]
+    fn skips_impossible_derives() {
+        let protocols = [Protocol::from_json(DOM).unwrap()];
+        let symbols = SymbolTable::new(&protocols);
+        let options = ["Eq", "std::hash::Hash", "Default", "Copy"]
+            .iter()
+            .fold(Options::new(), |options, derive| {
+                options.derive("*", *derive)
+            })
+            .attribute("DOM.Box", "#[serde(deny_unknown_fields)]")
+            .missing_fields(MissingFields::Strict);
+        let attributes = Attributes::new(&protocols, &symbols, &options);
+
+        let derives = |id: &str| -> Vec<&str> {
+            attributes.derivable[&format!("DOM.{}", id)]
+                .iter()
+                .copied()
+                .collect()
+        };
+        assert_eq!(derives("Rect"), ["Copy", "Default", "PartialEq"]);
+        assert_eq!(derives("Node"), ["Default", "Eq", "Hash", "PartialEq"]);
+        assert_eq!(derives("Box"), ["Copy", "PartialEq"]);
+
+        let element = protocols[0].domains[0].find_type("Box").unwrap();
+        let tokens = attributes.of_fields("DOM", "Box", element.properties.as_deref().unwrap());
+        assert_eq!(
+            tokens.to_string(),
+            quote! {
+                #[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Copy)]
+                #[serde(deny_unknown_fields)]
+            }
+            .to_string()
+        );
+    }
+
+    #[test]
+    fn derives_through_enums() {
+        let protocols = [
+            Protocol::from_json(include_str!("../json/js_protocol.json")).unwrap(),
+            Protocol::from_json(include_str!("../json/browser_protocol.json")).unwrap(),
+        ];
+        let symbols = SymbolTable::new(&protocols);
+        let options = Options::new()
+            .derive("Page.Frame", "Hash")
+            .derive("Page.Frame", "Eq")
+            .derive("Page.Viewport", "Eq");
+        let attributes = Attributes::new(&protocols, &symbols, &options);
+
+        // the enums of strings `Frame` holds have every derive without asking
+        let page = protocols[1].domain("Page").unwrap();
+        let frame = page.find_type("Frame").unwrap();
+        let tokens = attributes.of_fields("Page", "Frame", frame.properties.as_deref().unwrap());
+        assert_eq!(
+            tokens.to_string(),
+            quote! { #[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Hash, Eq)] }
+                .to_string()
+        );
+        assert!(attributes
+            .of_enum("Page", "AdFrameType")
+            .to_string()
+            .contains("Hash"));
+
+        // `Viewport` holds numbers
+        let viewport = page.find_type("Viewport").unwrap();
+        let tokens =
+            attributes.of_fields("Page", "Viewport", viewport.properties.as_deref().unwrap());
+        assert_eq!(
+            tokens.to_string(),
+            quote! { #[derive(Deserialize, Serialize, Debug, Clone, PartialEq)] }.to_string()
+        );
+        assert_eq!(
+            attributes.dropped("Page"),
+            ["`Page.Viewport` does not derive `Eq`, which a type it holds lacks"]
+        );
+        assert!(attributes.dropped("Network").is_empty());
+    }
+
+    #[test]
+    fn defaults_missing_fields() {
+        let protocols = [Protocol::from_json(DOM).unwrap()];
+        let symbols = SymbolTable::new(&protocols);
+        let options = Options::new().missing_fields(MissingFields::Lenient);
+        let attributes = Attributes::new(&protocols, &symbols, &options);
+        let element = protocols[0].domains[0].find_type("Box").unwrap();
+        let fields = element.properties.as_deref().unwrap();
+
+        assert!(attributes.derivable["DOM.Box"].contains("Default"));
+        assert!(fields
+            .iter()
+            .all(|field| attributes.has_default("DOM", "Box", field)));
+        assert!(attributes
+            .of_enum("DOM", "BoxKind")
+            .to_string()
+            .contains("Default"));
+
+        let options = Options::new();
+        let attributes = Attributes::new(&protocols, &symbols, &options);
+        assert!(!attributes.has_default("DOM", "Box", &fields[0]));
+        assert!(!attributes
+            .of_enum("DOM", "BoxKind")
+            .to_string()
+            .contains("Default"));
+    }
+}
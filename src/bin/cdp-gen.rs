@@ -0,0 +1,305 @@
+//! Generates the bindings ahead of time, e.g. to commit them instead of running
+//! `auto_generate_cdp` in a build script.
+
+use std::env;
+use std::path::PathBuf;
+use std::process;
+
+use auto_generate_cdp::{Format, FreeForm, Generator, MissingFields, Preset, Source, TimeCrate};
+
+const USAGE: &str = "\
+Usage: cdp-gen [OPTIONS] <OUT>
+
+Writes the Chrome DevTools Protocol bindings to the module file OUT, or a crate
+with a feature per domain to the directory OUT with --crate.
+
+Options:
+    --commit <COMMIT>          devtools-protocol commit to fetch the protocol from
+    --preset <PRESET>          chrome or node, the protocol to fetch at the commit
+    --json <FILE>              protocol file (.json or .pdl) to generate from instead,
+                               can be repeated
+    --endpoint <URL>           fetch the protocol a browser serves instead, e.g.
+                               http://localhost:9222/json/protocol
+    --extra-json <FILE>        protocol file generated along with the others, can be
+                               repeated
+    --overlay <FILE>           JSON-patch-style overlay applied before generating, can
+                               be repeated
+    --domain <DOMAIN>          only generate DOMAIN and the domains it depends on, can
+                               be repeated
+    --crate <NAME>             write a crate named NAME
+    --crate-version <VERSION>  version of the crate [default: 0.1.0]
+    --format <FORMAT>          plain, rustfmt or pretty
+    --borrowed                 generate types that borrow their strings and JSON
+                               values from the input
+    --raw-events               generate RawEvent, which decodes the parameters of an
+                               event only when asked
+    --time-types               generate the types holding times as wrappers that
+                               convert to SystemTime or Duration
+    --time-crate <CRATE>       chrono or time, also convert the time types to its
+                               types, can be repeated
+    --missing-fields <MODE>    strict or lenient, whether missing required fields are
+                               an error or get their default value [default: strict]
+    --free-form <PATH>=<FORM>  value, hash-map or btree-map, how to generate the
+                               objects without properties at PATH (*, Domain or
+                               Domain.name), can be repeated
+    --derive <PATH>=<DERIVE>   also derive DERIVE for the types at PATH (*, Domain or
+                               Domain.name), can be repeated
+    --attribute <PATH>=<ATTRIBUTE>
+                               add ATTRIBUTE to the types at PATH, can be repeated
+    --override-type <TYPE>=<PATH>
+                               use the Rust type at PATH instead of generating the
+                               protocol type TYPE (Domain.Type), can be repeated
+    -h, --help                 print this help
+";
+
+fn fail(message: &str) -> ! {
+    eprintln!("error: {}\n\n{}", message, USAGE);
+    process::exit(2);
+}
+
+fn parse_format(format: &str) -> Result<Format, String> {
+    match format {
+        "plain" => Ok(Format::Plain),
+        "rustfmt" => Ok(Format::Rustfmt),
+        #[cfg(feature = "pretty")]
+        "pretty" => Ok(Format::Pretty),
+        #[cfg(not(feature = "pretty"))]
+        "pretty" => Err(String::from(
+            "`pretty` needs cdp-gen to be built with the `pretty` feature",
+        )),
+        _ => Err(format!("unknown format `{}`", format)),
+    }
+}
+
+fn parse_preset(preset: &str) -> Result<Preset, String> {
+    match preset {
+        "chrome" => Ok(Preset::Chrome),
+        "node" => Ok(Preset::Node),
+        _ => Err(format!("unknown preset `{}`", preset)),
+    }
+}
+
+fn parse_time_crate(time_crate: &str) -> Result<TimeCrate, String> {
+    match time_crate {
+        "chrono" => Ok(TimeCrate::Chrono),
+        "time" => Ok(TimeCrate::Time),
+        _ => Err(format!("unknown time crate `{}`", time_crate)),
+    }
+}
+
+fn parse_missing_fields(mode: &str) -> Result<MissingFields, String> {
+    match mode {
+        "strict" => Ok(MissingFields::Strict),
+        "lenient" => Ok(MissingFields::Lenient),
+        _ => Err(format!("unknown missing fields mode `{}`", mode)),
+    }
+}
+
+fn parse_free_form(form: &str) -> Result<FreeForm, String> {
+    match form {
+        "value" => Ok(FreeForm::Value),
+        "hash-map" => Ok(FreeForm::HashMap),
+        "btree-map" => Ok(FreeForm::BTreeMap),
+        _ => Err(format!("unknown free-form object form `{}`", form)),
+    }
+}
+
+/// Splits `value` at its first `=`, as in `<PATH>=<FORM>`.
+fn split_pair<'a>(value: &'a str, form: &str) -> Result<(&'a str, &'a str), String> {
+    value
+        .split_once('=')
+        .ok_or_else(|| format!("`{}` isn't of the form {}", value, form))
+}
+
+/// What the arguments ask to generate.
+#[derive(Debug)]
+struct Command {
+    generator: Generator,
+    out: PathBuf,
+    /// Name of the crate to write to `out` instead of a module file.
+    crate_name: Option<String>,
+    crate_version: String,
+}
+
+/// Parses the arguments, without the program name. `None` is a request for help.
+fn parse_args<I: IntoIterator<Item = String>>(args: I) -> Result<Option<Command>, String> {
+    let mut generator = Generator::new();
+    let mut files = Vec::new();
+    let mut extra_files = Vec::new();
+    let mut overlays = Vec::new();
+    let mut domains = Vec::new();
+    let mut crate_name = None;
+    let mut crate_version = String::from("0.1.0");
+    let mut out = None;
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("`{}` needs a value", arg))
+        };
+
+        match arg.as_str() {
+            "--commit" => generator = generator.commit(&value()?),
+            "--preset" => generator = generator.preset(parse_preset(&value()?)?),
+            "--json" => files.push(PathBuf::from(value()?)),
+            "--extra-json" => extra_files.push(PathBuf::from(value()?)),
+            "--overlay" => overlays.push(PathBuf::from(value()?)),
+            "--endpoint" => generator = generator.source(Source::Endpoint(value()?)),
+            "--domain" => domains.push(value()?),
+            "--crate" => crate_name = Some(value()?),
+            "--crate-version" => crate_version = value()?,
+            "--format" => generator = generator.format(parse_format(&value()?)?),
+            "--borrowed" => generator = generator.borrowed(true),
+            "--raw-events" => generator = generator.raw_events(true),
+            "--time-types" => generator = generator.time_types(true),
+            "--time-crate" => {
+                generator = generator
+                    .time_types(true)
+                    .time_crate(parse_time_crate(&value()?)?)
+            }
+            "--missing-fields" => {
+                generator = generator.missing_fields(parse_missing_fields(&value()?)?)
+            }
+            "--free-form" => {
+                let value = value()?;
+                let (path, form) = split_pair(&value, "<PATH>=<FORM>")?;
+                generator = generator.free_form(path, parse_free_form(form)?);
+            }
+            "--derive" => {
+                let value = value()?;
+                let (path, derive) = split_pair(&value, "<PATH>=<DERIVE>")?;
+                generator = generator.derive(path, derive);
+            }
+            "--attribute" => {
+                let value = value()?;
+                let (path, attribute) = split_pair(&value, "<PATH>=<ATTRIBUTE>")?;
+                generator = generator.attribute(path, attribute);
+            }
+            "--override-type" => {
+                let value = value()?;
+                let (path, rust_type) = split_pair(&value, "<TYPE>=<PATH>")?;
+                generator = generator.override_type(path, rust_type);
+            }
+            "-h" | "--help" => return Ok(None),
+            _ if arg.starts_with('-') => return Err(format!("unknown option `{}`", arg)),
+            _ if out.is_none() => out = Some(PathBuf::from(arg)),
+            _ => return Err(format!("unexpected argument `{}`", arg)),
+        }
+    }
+
+    let out = out.ok_or_else(|| String::from("missing <OUT>"))?;
+    if !files.is_empty() {
+        generator = generator.files(files);
+    }
+    generator = generator
+        .extra_files(extra_files)
+        .overlays(overlays)
+        .domains(domains);
+
+    Ok(Some(Command {
+        generator,
+        out,
+        crate_name,
+        crate_version,
+    }))
+}
+
+fn main() {
+    let command = match parse_args(env::args().skip(1)) {
+        Ok(Some(command)) => command,
+        Ok(None) => {
+            print!("{}", USAGE);
+            return;
+        }
+        Err(err) => fail(&err),
+    };
+
+    match command.crate_name {
+        Some(name) => command
+            .generator
+            .generate_crate(&command.out, &name, &command.crate_version),
+        None => command.generator.out_file(command.out).write(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Option<Command>, String> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn parses_arguments() {
+        let command = parse(&[
+            "--json",
+            "browser_protocol.json",
+            "--override-type",
+            "Network.Headers=http::HeaderMap",
+            "--free-form",
+            "Tracing.MemoryDumpConfig=hash-map",
+            "--missing-fields",
+            "lenient",
+            "--domain",
+            "Page",
+            "--derive",
+            "Page=Eq",
+            "--attribute",
+            "Page.FrameTree=#[serde(rename_all = \"camelCase\")]",
+            "--domain",
+            "Network",
+            "--crate",
+            "cdp-bindings",
+            "out",
+        ])
+        .unwrap()
+        .unwrap();
+
+        let expected = Generator::new()
+            .override_type("Network.Headers", "http::HeaderMap")
+            .free_form("Tracing.MemoryDumpConfig", FreeForm::HashMap)
+            .missing_fields(MissingFields::Lenient)
+            .derive("Page", "Eq")
+            .attribute("Page.FrameTree", "#[serde(rename_all = \"camelCase\")]")
+            .files(["browser_protocol.json"])
+            .domains(["Page", "Network"]);
+        assert_eq!(
+            format!("{:?}", command.generator),
+            format!("{:?}", expected)
+        );
+        assert_eq!(command.out, PathBuf::from("out"));
+        assert_eq!(command.crate_name.as_deref(), Some("cdp-bindings"));
+        assert_eq!(command.crate_version, "0.1.0");
+
+        // only the first `=` separates the path, as Rust paths can't contain one
+        let command = parse(&["--override-type", "A.B=C<D=E>", "out"])
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            format!("{:?}", command.generator),
+            format!("{:?}", Generator::new().override_type("A.B", "C<D=E>"))
+        );
+
+        assert!(parse(&["--help"]).unwrap().is_none());
+    }
+
+    #[test]
+    fn reports_invalid_arguments() {
+        let err = |args: &[&str]| parse(args).unwrap_err();
+
+        assert_eq!(
+            err(&["--override-type", "Network.Headers", "out"]),
+            "`Network.Headers` isn't of the form <TYPE>=<PATH>"
+        );
+        assert_eq!(
+            err(&["--free-form", "Tracing=list", "out"]),
+            "unknown free-form object form `list`"
+        );
+        assert_eq!(err(&["--free-form"]), "`--free-form` needs a value");
+        assert_eq!(err(&["--verbose", "out"]), "unknown option `--verbose`");
+        assert_eq!(err(&["out", "more"]), "unexpected argument `more`");
+        assert_eq!(err(&[]), "missing <OUT>");
+    }
+}
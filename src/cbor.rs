@@ -0,0 +1,793 @@
+//! The `cbor` feature: Chromium's binary encoding of the protocol messages, as its
+//! crdtp layer reads and writes them.
+//!
+//! Maps are indefinite-length and wrapped in an envelope, a byte string tagged 24
+//! with a 32-bit length. Arrays are indefinite-length, integers outside of `i32` are
+//! doubles and binary data is a byte string tagged 22. Byte strings without a tag
+//! are UTF-16LE strings.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::types::{Parameter, TypeEnum};
+
+/// How the descriptions of the protocol mark strings holding binary data.
+pub const BINARY_NOTE: &str = "Encoded as a base64 string when passed over JSON";
+
+/// The `cbor` module of `cdp::types`, nothing without the `cbor` feature.
+pub fn module() -> TokenStream {
+    if !cfg!(feature = "cbor") {
+        return quote! {};
+    }
+
+    quote! {
+        /// Chromium's CBOR encoding of the protocol messages, with [`to_vec`] and
+        /// [`from_slice`] in place of `serde_json::to_string` and `serde_json::from_str`.
+        pub mod cbor {
+            use serde::{de, ser, Deserialize, Serialize};
+            use std::convert::TryFrom;
+            use std::fmt;
+
+            const ENVELOPE: [u8; 2] = [0xd8, 0x18];
+            const ENVELOPE_SIZE: u8 = 0x5a;
+            const BINARY_TAG: u8 = 0xd6;
+            const ARRAY_START: u8 = 0x9f;
+            const MAP_START: u8 = 0xbf;
+            const STOP: u8 = 0xff;
+            const FALSE: u8 = 0xf4;
+            const TRUE: u8 = 0xf5;
+            const NULL: u8 = 0xf6;
+            const FLOAT: u8 = 0xfa;
+            const DOUBLE: u8 = 0xfb;
+
+            /// The newtype [`binary`] wraps base64 strings in, which other formats
+            /// see through.
+            const BINARY: &str = "$cdp::cbor::binary";
+
+            const BASE64: &[u8; 64] =
+                b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+            #[derive(Debug, Clone, PartialEq)]
+            pub struct Error(String);
+
+            impl fmt::Display for Error {
+                fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                    formatter.write_str(&self.0)
+                }
+            }
+
+            impl std::error::Error for Error {}
+
+            impl ser::Error for Error {
+                fn custom<T: fmt::Display>(message: T) -> Self {
+                    Error(message.to_string())
+                }
+            }
+
+            impl de::Error for Error {
+                fn custom<T: fmt::Display>(message: T) -> Self {
+                    Error(message.to_string())
+                }
+            }
+
+            type Result<T> = std::result::Result<T, Error>;
+
+            pub fn to_vec<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>> {
+                let mut serializer = Serializer {
+                    out: Vec::new(),
+                    binary: false,
+                };
+                value.serialize(&mut serializer)?;
+                Ok(serializer.out)
+            }
+
+            pub fn from_slice<'de, T: Deserialize<'de>>(bytes: &'de [u8]) -> Result<T> {
+                let mut deserializer = Deserializer { input: bytes };
+                let value = T::deserialize(&mut deserializer)?;
+                if !deserializer.input.is_empty() {
+                    return Err(Error(format!("{} trailing bytes", deserializer.input.len())));
+                }
+                Ok(value)
+            }
+
+            /// Serializes a base64 string as binary data, which stays a string in
+            /// other formats.
+            pub fn binary<T: AsRef<str>, S: ser::Serializer>(value: &T, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+                serializer.serialize_newtype_struct(BINARY, value.as_ref())
+            }
+
+            /// [`binary`] of optional fields.
+            pub fn option_binary<T: AsRef<str>, S: ser::Serializer>(value: &Option<T>, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+                struct Binary<'a>(&'a str);
+
+                impl Serialize for Binary<'_> {
+                    fn serialize<S: ser::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+                        binary(&self.0, serializer)
+                    }
+                }
+
+                match value {
+                    Some(value) => serializer.serialize_some(&Binary(value.as_ref())),
+                    None => serializer.serialize_none(),
+                }
+            }
+
+            fn base64_encode(bytes: &[u8]) -> String {
+                let mut encoded = String::with_capacity(bytes.len() / 3 * 4 + 4);
+                for chunk in bytes.chunks(3) {
+                    let n = chunk
+                        .iter()
+                        .enumerate()
+                        .fold(0u32, |n, (i, byte)| n | u32::from(*byte) << (16 - 8 * i));
+                    for i in 0..4 {
+                        if i <= chunk.len() {
+                            encoded.push(BASE64[(n >> (18 - 6 * i) & 63) as usize] as char);
+                        } else {
+                            encoded.push('=');
+                        }
+                    }
+                }
+                encoded
+            }
+
+            fn base64_decode(encoded: &str) -> Option<Vec<u8>> {
+                let encoded = encoded.as_bytes();
+                if encoded.len() % 4 != 0 {
+                    return None;
+                }
+
+                let mut bytes = Vec::with_capacity(encoded.len() / 4 * 3);
+                let chunks = encoded.len() / 4;
+                for (i, chunk) in encoded.chunks(4).enumerate() {
+                    let mut n = 0u32;
+                    let mut padding = 0;
+                    for (j, c) in chunk.iter().enumerate() {
+                        let value = match c {
+                            _ if padding > 0 && *c != b'=' => return None,
+                            b'A'..=b'Z' => c - b'A',
+                            b'a'..=b'z' => c - b'a' + 26,
+                            b'0'..=b'9' => c - b'0' + 52,
+                            b'+' => 62,
+                            b'/' => 63,
+                            b'=' if i + 1 == chunks && j >= 2 => {
+                                padding += 1;
+                                0
+                            }
+                            _ => return None,
+                        };
+                        n = n << 6 | u32::from(value);
+                    }
+                    bytes.extend_from_slice(&n.to_be_bytes()[1..4 - padding]);
+                }
+                Some(bytes)
+            }
+
+            pub struct Serializer {
+                out: Vec<u8>,
+                /// Whether the next string is base64 to write as binary data.
+                binary: bool,
+            }
+
+            impl Serializer {
+                fn head(&mut self, major: u8, value: u64) {
+                    let major = major << 5;
+                    if value < 24 {
+                        self.out.push(major | value as u8);
+                    } else if value <= u64::from(u8::MAX) {
+                        self.out.push(major | 24);
+                        self.out.push(value as u8);
+                    } else if value <= u64::from(u16::MAX) {
+                        self.out.push(major | 25);
+                        self.out.extend_from_slice(&(value as u16).to_be_bytes());
+                    } else if value <= u64::from(u32::MAX) {
+                        self.out.push(major | 26);
+                        self.out.extend_from_slice(&(value as u32).to_be_bytes());
+                    } else {
+                        self.out.push(major | 27);
+                        self.out.extend_from_slice(&value.to_be_bytes());
+                    }
+                }
+
+                fn int(&mut self, value: i64) {
+                    if value < i64::from(i32::MIN) || value > i64::from(i32::MAX) {
+                        self.double(value as f64);
+                    } else if value >= 0 {
+                        self.head(0, value as u64);
+                    } else {
+                        self.head(1, (-1 - value) as u64);
+                    }
+                }
+
+                fn double(&mut self, value: f64) {
+                    self.out.push(DOUBLE);
+                    self.out.extend_from_slice(&value.to_be_bytes());
+                }
+
+                fn bytes(&mut self, value: &[u8]) {
+                    self.out.push(BINARY_TAG);
+                    self.head(2, value.len() as u64);
+                    self.out.extend_from_slice(value);
+                }
+
+                /// Starts an enveloped map, returning where its size goes.
+                fn begin_map(&mut self) -> usize {
+                    self.out.extend_from_slice(&ENVELOPE);
+                    self.out.push(ENVELOPE_SIZE);
+                    let size = self.out.len();
+                    self.out.extend_from_slice(&[0; 4]);
+                    self.out.push(MAP_START);
+                    size
+                }
+
+                fn end_map(&mut self, size: usize) -> Result<()> {
+                    self.out.push(STOP);
+                    let length = u32::try_from(self.out.len() - size - 4)
+                        .map_err(|_| Error(String::from("map too large for its envelope")))?;
+                    self.out[size..size + 4].copy_from_slice(&length.to_be_bytes());
+                    Ok(())
+                }
+            }
+
+            /// A sequence or map being serialized, with the envelopes to close
+            /// after it.
+            pub struct Compound<'a> {
+                serializer: &'a mut Serializer,
+                array: bool,
+                map: Option<usize>,
+                variant: Option<usize>,
+            }
+
+            impl Compound<'_> {
+                fn end(self) -> Result<()> {
+                    if self.array {
+                        self.serializer.out.push(STOP);
+                    }
+                    if let Some(size) = self.map {
+                        self.serializer.end_map(size)?;
+                    }
+                    if let Some(size) = self.variant {
+                        self.serializer.end_map(size)?;
+                    }
+                    Ok(())
+                }
+            }
+
+            impl<'a> ser::Serializer for &'a mut Serializer {
+                type Ok = ();
+                type Error = Error;
+                type SerializeSeq = Compound<'a>;
+                type SerializeTuple = Compound<'a>;
+                type SerializeTupleStruct = Compound<'a>;
+                type SerializeTupleVariant = Compound<'a>;
+                type SerializeMap = Compound<'a>;
+                type SerializeStruct = Compound<'a>;
+                type SerializeStructVariant = Compound<'a>;
+
+                fn serialize_bool(self, value: bool) -> Result<()> {
+                    self.out.push(if value { TRUE } else { FALSE });
+                    Ok(())
+                }
+
+                fn serialize_i8(self, value: i8) -> Result<()> {
+                    self.serialize_i64(i64::from(value))
+                }
+
+                fn serialize_i16(self, value: i16) -> Result<()> {
+                    self.serialize_i64(i64::from(value))
+                }
+
+                fn serialize_i32(self, value: i32) -> Result<()> {
+                    self.serialize_i64(i64::from(value))
+                }
+
+                fn serialize_i64(self, value: i64) -> Result<()> {
+                    self.int(value);
+                    Ok(())
+                }
+
+                fn serialize_u8(self, value: u8) -> Result<()> {
+                    self.serialize_i64(i64::from(value))
+                }
+
+                fn serialize_u16(self, value: u16) -> Result<()> {
+                    self.serialize_i64(i64::from(value))
+                }
+
+                fn serialize_u32(self, value: u32) -> Result<()> {
+                    self.serialize_i64(i64::from(value))
+                }
+
+                fn serialize_u64(self, value: u64) -> Result<()> {
+                    match i64::try_from(value) {
+                        Ok(value) => self.int(value),
+                        Err(_) => self.double(value as f64),
+                    }
+                    Ok(())
+                }
+
+                fn serialize_f32(self, value: f32) -> Result<()> {
+                    self.serialize_f64(f64::from(value))
+                }
+
+                fn serialize_f64(self, value: f64) -> Result<()> {
+                    self.double(value);
+                    Ok(())
+                }
+
+                fn serialize_char(self, value: char) -> Result<()> {
+                    self.serialize_str(value.encode_utf8(&mut [0; 4]))
+                }
+
+                fn serialize_str(self, value: &str) -> Result<()> {
+                    if std::mem::take(&mut self.binary) {
+                        if let Some(bytes) = base64_decode(value) {
+                            self.bytes(&bytes);
+                            return Ok(());
+                        }
+                    }
+                    self.head(3, value.len() as u64);
+                    self.out.extend_from_slice(value.as_bytes());
+                    Ok(())
+                }
+
+                fn serialize_bytes(self, value: &[u8]) -> Result<()> {
+                    self.bytes(value);
+                    Ok(())
+                }
+
+                fn serialize_none(self) -> Result<()> {
+                    self.serialize_unit()
+                }
+
+                fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<()> {
+                    value.serialize(self)
+                }
+
+                fn serialize_unit(self) -> Result<()> {
+                    self.out.push(NULL);
+                    Ok(())
+                }
+
+                fn serialize_unit_struct(self, _name: &'static str) -> Result<()> {
+                    self.serialize_unit()
+                }
+
+                fn serialize_unit_variant(self, _name: &'static str, _index: u32, variant: &'static str) -> Result<()> {
+                    self.serialize_str(variant)
+                }
+
+                fn serialize_newtype_struct<T: Serialize + ?Sized>(self, name: &'static str, value: &T) -> Result<()> {
+                    self.binary = name == BINARY;
+                    let result = value.serialize(&mut *self);
+                    self.binary = false;
+                    result
+                }
+
+                fn serialize_newtype_variant<T: Serialize + ?Sized>(self, _name: &'static str, _index: u32, variant: &'static str, value: &T) -> Result<()> {
+                    let size = self.begin_map();
+                    self.serialize_str(variant)?;
+                    value.serialize(&mut *self)?;
+                    self.end_map(size)
+                }
+
+                fn serialize_seq(self, _len: Option<usize>) -> Result<Compound<'a>> {
+                    self.out.push(ARRAY_START);
+                    Ok(Compound { serializer: self, array: true, map: None, variant: None })
+                }
+
+                fn serialize_tuple(self, len: usize) -> Result<Compound<'a>> {
+                    self.serialize_seq(Some(len))
+                }
+
+                fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<Compound<'a>> {
+                    self.serialize_seq(Some(len))
+                }
+
+                fn serialize_tuple_variant(self, _name: &'static str, _index: u32, variant: &'static str, _len: usize) -> Result<Compound<'a>> {
+                    let size = self.begin_map();
+                    self.serialize_str(variant)?;
+                    self.out.push(ARRAY_START);
+                    Ok(Compound { serializer: self, array: true, map: None, variant: Some(size) })
+                }
+
+                fn serialize_map(self, _len: Option<usize>) -> Result<Compound<'a>> {
+                    let size = self.begin_map();
+                    Ok(Compound { serializer: self, array: false, map: Some(size), variant: None })
+                }
+
+                fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Compound<'a>> {
+                    self.serialize_map(None)
+                }
+
+                fn serialize_struct_variant(self, _name: &'static str, _index: u32, variant: &'static str, _len: usize) -> Result<Compound<'a>> {
+                    let variant_size = self.begin_map();
+                    self.serialize_str(variant)?;
+                    let size = self.begin_map();
+                    Ok(Compound { serializer: self, array: false, map: Some(size), variant: Some(variant_size) })
+                }
+            }
+
+            impl ser::SerializeSeq for Compound<'_> {
+                type Ok = ();
+                type Error = Error;
+
+                fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+                    value.serialize(&mut *self.serializer)
+                }
+
+                fn end(self) -> Result<()> {
+                    Compound::end(self)
+                }
+            }
+
+            impl ser::SerializeTuple for Compound<'_> {
+                type Ok = ();
+                type Error = Error;
+
+                fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+                    value.serialize(&mut *self.serializer)
+                }
+
+                fn end(self) -> Result<()> {
+                    Compound::end(self)
+                }
+            }
+
+            impl ser::SerializeTupleStruct for Compound<'_> {
+                type Ok = ();
+                type Error = Error;
+
+                fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+                    value.serialize(&mut *self.serializer)
+                }
+
+                fn end(self) -> Result<()> {
+                    Compound::end(self)
+                }
+            }
+
+            impl ser::SerializeTupleVariant for Compound<'_> {
+                type Ok = ();
+                type Error = Error;
+
+                fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+                    value.serialize(&mut *self.serializer)
+                }
+
+                fn end(self) -> Result<()> {
+                    Compound::end(self)
+                }
+            }
+
+            impl ser::SerializeMap for Compound<'_> {
+                type Ok = ();
+                type Error = Error;
+
+                fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<()> {
+                    key.serialize(&mut *self.serializer)
+                }
+
+                fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+                    value.serialize(&mut *self.serializer)
+                }
+
+                fn end(self) -> Result<()> {
+                    Compound::end(self)
+                }
+            }
+
+            impl ser::SerializeStruct for Compound<'_> {
+                type Ok = ();
+                type Error = Error;
+
+                fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<()> {
+                    ser::Serializer::serialize_str(&mut *self.serializer, key)?;
+                    value.serialize(&mut *self.serializer)
+                }
+
+                fn end(self) -> Result<()> {
+                    Compound::end(self)
+                }
+            }
+
+            impl ser::SerializeStructVariant for Compound<'_> {
+                type Ok = ();
+                type Error = Error;
+
+                fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<()> {
+                    ser::Serializer::serialize_str(&mut *self.serializer, key)?;
+                    value.serialize(&mut *self.serializer)
+                }
+
+                fn end(self) -> Result<()> {
+                    Compound::end(self)
+                }
+            }
+
+            pub struct Deserializer<'de> {
+                input: &'de [u8],
+            }
+
+            impl<'de> Deserializer<'de> {
+                fn peek(&self) -> Result<u8> {
+                    self.input
+                        .first()
+                        .copied()
+                        .ok_or_else(|| Error(String::from("unexpected end of input")))
+                }
+
+                fn next(&mut self) -> Result<u8> {
+                    let byte = self.peek()?;
+                    self.input = &self.input[1..];
+                    Ok(byte)
+                }
+
+                fn take(&mut self, len: u64) -> Result<&'de [u8]> {
+                    match usize::try_from(len) {
+                        Ok(len) if len <= self.input.len() => {
+                            let (taken, rest) = self.input.split_at(len);
+                            self.input = rest;
+                            Ok(taken)
+                        }
+                        _ => Err(Error(String::from("unexpected end of input"))),
+                    }
+                }
+
+                /// The value in the head starting with `initial`.
+                fn argument(&mut self, initial: u8) -> Result<u64> {
+                    let be = |bytes: &[u8]| bytes.iter().fold(0, |n, byte| n << 8 | u64::from(*byte));
+                    match initial & 0x1f {
+                        info @ 0..=23 => Ok(u64::from(info)),
+                        24 => self.take(1).map(be),
+                        25 => self.take(2).map(be),
+                        26 => self.take(4).map(be),
+                        27 => self.take(8).map(be),
+                        _ => Err(Error(format!("unsupported length in {:#04x}", initial))),
+                    }
+                }
+
+                fn byte_string(&mut self) -> Result<&'de [u8]> {
+                    let initial = self.next()?;
+                    if initial >> 5 != 2 {
+                        return Err(Error(format!("expected a byte string, found {:#04x}", initial)));
+                    }
+                    let len = self.argument(initial)?;
+                    self.take(len)
+                }
+
+                /// Skips the envelope of a map, returning how much input is left
+                /// after it.
+                fn begin_envelope(&mut self) -> Result<Option<usize>> {
+                    if !self.input.starts_with(&ENVELOPE) {
+                        return Ok(None);
+                    }
+                    self.input = &self.input[ENVELOPE.len()..];
+                    let initial = self.next()?;
+                    if initial >> 5 != 2 {
+                        return Err(Error(format!("expected an envelope, found {:#04x}", initial)));
+                    }
+                    let len = self.argument(initial)?;
+                    match usize::try_from(len) {
+                        Ok(len) if len <= self.input.len() => Ok(Some(self.input.len() - len)),
+                        _ => Err(Error(String::from("envelope larger than the input"))),
+                    }
+                }
+
+                fn end_envelope(&self, rest: Option<usize>) -> Result<()> {
+                    match rest {
+                        Some(rest) if rest != self.input.len() => {
+                            Err(Error(String::from("envelope size doesn't match its contents")))
+                        }
+                        _ => Ok(()),
+                    }
+                }
+
+                fn parse<V: de::Visitor<'de>>(&mut self, visitor: V) -> Result<V::Value> {
+                    let initial = self.next()?;
+                    match initial >> 5 {
+                        0 => visitor.visit_u64(self.argument(initial)?),
+                        1 => {
+                            let n = self.argument(initial)?;
+                            let n = i64::try_from(n).map_err(|_| Error(String::from("integer out of range")))?;
+                            visitor.visit_i64(-1 - n)
+                        }
+                        2 => {
+                            let len = self.argument(initial)?;
+                            let bytes = self.take(len)?;
+                            if bytes.len() % 2 != 0 {
+                                return Err(Error(String::from("odd length UTF-16 string")));
+                            }
+                            let units: Vec<u16> = bytes
+                                .chunks_exact(2)
+                                .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+                                .collect();
+                            let string = String::from_utf16(&units).map_err(|error| Error(error.to_string()))?;
+                            visitor.visit_string(string)
+                        }
+                        3 => {
+                            let len = self.argument(initial)?;
+                            let bytes = self.take(len)?;
+                            let string = std::str::from_utf8(bytes).map_err(|error| Error(error.to_string()))?;
+                            visitor.visit_borrowed_str(string)
+                        }
+                        4 => {
+                            let len = if initial == ARRAY_START { None } else { Some(self.argument(initial)?) };
+                            visitor.visit_seq(Access { deserializer: self, len })
+                        }
+                        5 => {
+                            let len = if initial == MAP_START { None } else { Some(self.argument(initial)?) };
+                            visitor.visit_map(Access { deserializer: self, len })
+                        }
+                        _ => match initial {
+                            BINARY_TAG => {
+                                let bytes = self.byte_string()?;
+                                visitor.visit_string(base64_encode(bytes))
+                            }
+                            FALSE => visitor.visit_bool(false),
+                            TRUE => visitor.visit_bool(true),
+                            NULL => visitor.visit_unit(),
+                            FLOAT => {
+                                let bytes = self.take(4)?;
+                                visitor.visit_f32(f32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+                            }
+                            DOUBLE => {
+                                let mut bytes = [0; 8];
+                                bytes.copy_from_slice(self.take(8)?);
+                                visitor.visit_f64(f64::from_be_bytes(bytes))
+                            }
+                            _ => Err(Error(format!("unexpected {:#04x}", initial))),
+                        },
+                    }
+                }
+            }
+
+            impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
+                type Error = Error;
+
+                fn deserialize_any<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+                    let rest = self.begin_envelope()?;
+                    let value = self.parse(visitor)?;
+                    self.end_envelope(rest)?;
+                    Ok(value)
+                }
+
+                fn deserialize_option<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+                    if self.peek()? == NULL {
+                        self.next()?;
+                        visitor.visit_none()
+                    } else {
+                        visitor.visit_some(self)
+                    }
+                }
+
+                fn deserialize_newtype_struct<V: de::Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value> {
+                    visitor.visit_newtype_struct(self)
+                }
+
+                fn deserialize_enum<V: de::Visitor<'de>>(self, _name: &'static str, _variants: &'static [&'static str], visitor: V) -> Result<V::Value> {
+                    let rest = self.begin_envelope()?;
+                    if rest.is_none() {
+                        return visitor.visit_enum(de::value::StringDeserializer::new(String::deserialize(self)?));
+                    }
+
+                    if self.next()? != MAP_START {
+                        return Err(Error(String::from("expected a map of one variant")));
+                    }
+                    let value = visitor.visit_enum(&mut *self)?;
+                    if self.next()? != STOP {
+                        return Err(Error(String::from("expected a map of one variant")));
+                    }
+                    self.end_envelope(rest)?;
+                    Ok(value)
+                }
+
+                serde::forward_to_deserialize_any! {
+                    bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+                    bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+                    identifier ignored_any
+                }
+            }
+
+            /// The elements of an array or the entries of a map, `len` of them or
+            /// up to a stop byte.
+            struct Access<'a, 'de> {
+                deserializer: &'a mut Deserializer<'de>,
+                len: Option<u64>,
+            }
+
+            impl Access<'_, '_> {
+                fn has_next(&mut self) -> Result<bool> {
+                    match &mut self.len {
+                        Some(0) => Ok(false),
+                        Some(len) => {
+                            *len -= 1;
+                            Ok(true)
+                        }
+                        None if self.deserializer.peek()? == STOP => {
+                            self.deserializer.next()?;
+                            Ok(false)
+                        }
+                        None => Ok(true),
+                    }
+                }
+            }
+
+            impl<'de> de::SeqAccess<'de> for Access<'_, 'de> {
+                type Error = Error;
+
+                fn next_element_seed<T: de::DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>> {
+                    if self.has_next()? {
+                        seed.deserialize(&mut *self.deserializer).map(Some)
+                    } else {
+                        Ok(None)
+                    }
+                }
+            }
+
+            impl<'de> de::MapAccess<'de> for Access<'_, 'de> {
+                type Error = Error;
+
+                fn next_key_seed<K: de::DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
+                    if self.has_next()? {
+                        seed.deserialize(&mut *self.deserializer).map(Some)
+                    } else {
+                        Ok(None)
+                    }
+                }
+
+                fn next_value_seed<V: de::DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value> {
+                    seed.deserialize(&mut *self.deserializer)
+                }
+            }
+
+            impl<'de> de::EnumAccess<'de> for &mut Deserializer<'de> {
+                type Error = Error;
+                type Variant = Self;
+
+                fn variant_seed<V: de::DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Self)> {
+                    let variant = seed.deserialize(&mut *self)?;
+                    Ok((variant, self))
+                }
+            }
+
+            impl<'de> de::VariantAccess<'de> for &mut Deserializer<'de> {
+                type Error = Error;
+
+                fn unit_variant(self) -> Result<()> {
+                    de::IgnoredAny::deserialize(self).map(|_| ())
+                }
+
+                fn newtype_variant_seed<T: de::DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value> {
+                    seed.deserialize(self)
+                }
+
+                fn tuple_variant<V: de::Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value> {
+                    de::Deserializer::deserialize_seq(self, visitor)
+                }
+
+                fn struct_variant<V: de::Visitor<'de>>(self, _fields: &'static [&'static str], visitor: V) -> Result<V::Value> {
+                    de::Deserializer::deserialize_map(self, visitor)
+                }
+            }
+        }
+    }
+}
+
+/// `#[serde(serialize_with)]` of `field` if it holds binary data, which the `cbor`
+/// feature encodes as bytes instead of base64.
+pub fn field_attributes(field: &Parameter) -> TokenStream {
+    let binary = matches!(field.parameter_type, Some(TypeEnum::String))
+        && field
+            .description
+            .as_deref()
+            .is_some_and(|description| description.contains(BINARY_NOTE));
+    if !cfg!(feature = "cbor") || !binary {
+        return quote! {};
+    }
+
+    if field.optional.unwrap_or(false) {
+        quote! { #[serde(serialize_with = "cbor::option_binary")] }
+    } else {
+        quote! { #[serde(serialize_with = "cbor::binary")] }
+    }
+}
@@ -0,0 +1,583 @@
+//! Generating the bindings from the [`model`](crate::types), for building a
+//! different layout or client on top of the generated domains.
+//!
+//! ```no_run
+//! use auto_generate_cdp::{codegen, types::Protocol};
+//!
+//! let json = std::fs::read_to_string("browser_protocol.json").unwrap();
+//! let protocol = Protocol::from_json(&json).unwrap();
+//!
+//! for module in codegen::select(codegen::domains(&[protocol]), &["Page".into()]) {
+//!     println!("{} depends on {:?}", module.domain, module.dependencies);
+//! }
+//! ```
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::compile::compile_cdp_json;
+pub use crate::compile::DomainModule;
+use crate::types::Protocol;
+
+/// How the domains are generated, see [`domains_with`].
+#[derive(Clone, Debug, Default)]
+pub struct Options {
+    pub(crate) derives: Vec<(String, String)>,
+    pub(crate) attributes: Vec<(String, String)>,
+    pub(crate) borrowed: bool,
+    pub(crate) raw_events: bool,
+    pub(crate) time_types: bool,
+    pub(crate) time_crates: Vec<TimeCrate>,
+    pub(crate) overrides: BTreeMap<String, String>,
+    pub(crate) free_forms: Vec<(String, FreeForm)>,
+    pub(crate) missing_fields: MissingFields,
+}
+
+/// What happens to the required fields missing from the input, see
+/// [`Options::missing_fields`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MissingFields {
+    /// They are an error. The default since 0.5, before which missing strings,
+    /// numbers and booleans got their default value.
+    #[default]
+    Strict,
+    /// They get their default value, which every type and enum has unless it holds a
+    /// borrowed JSON value or an [overridden type](Options::override_type).
+    Lenient,
+}
+
+/// How an object without properties is generated, see [`Options::free_form`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FreeForm {
+    /// A wrapper of an optional `serde_json::Value`.
+    Value,
+    /// A `HashMap` of strings to strings for headers, or to JSON values.
+    HashMap,
+    /// A `BTreeMap` of strings to strings for headers, or to JSON values.
+    BTreeMap,
+}
+
+/// A crate whose types the [time types](Options::time_types) convert to as well.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimeCrate {
+    /// `chrono` 0.4, converting to `DateTime<Utc>` and `chrono::Duration`.
+    Chrono,
+    /// `time` 0.3, converting to `OffsetDateTime` and `time::Duration`.
+    Time,
+}
+
+impl Options {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Derives `derive`, e.g. `Eq` or `schemars::JsonSchema`, for the types at
+    /// `path`: `*` for all of them, a domain such as `Page` for its types, or
+    /// `Domain.name` for a type, the parameters and return object of a command or an
+    /// event, including the enums of their fields.
+    ///
+    /// `Eq`, `Hash`, `PartialOrd`, `Ord`, `Default` and `Copy` are left out of the
+    /// types that cannot have them, e.g. `Eq` of the ones holding a `JsFloat`, as
+    /// well as of the types referring to those.
+    pub fn derive(mut self, path: impl Into<String>, derive: impl Into<String>) -> Self {
+        self.derives.push((path.into(), derive.into()));
+        self
+    }
+
+    /// Adds `attribute`, e.g. `#[serde(deny_unknown_fields)]`, to the types at
+    /// `path` as [`derive`](Options::derive) selects them.
+    pub fn attribute(mut self, path: impl Into<String>, attribute: impl Into<String>) -> Self {
+        self.attributes.push((path.into(), attribute.into()));
+        self
+    }
+
+    /// Generates the types with a lifetime `'a` that borrow their strings as
+    /// `Cow<'a, str>` and their JSON values as `&'a RawValue` from the input, instead
+    /// of owning them. Needs the `raw_value` feature of `serde_json`.
+    ///
+    /// `Method::ReturnObject<'de>` then borrows from the response, and `Event<'a>` can
+    /// only be deserialized with `serde_json`. The types holding a `RawValue` don't
+    /// derive `PartialEq`, nor the derives that need it. Implies
+    /// [`raw_events`](Options::raw_events).
+    pub fn borrowed(mut self, borrowed: bool) -> Self {
+        self.borrowed = borrowed;
+        self
+    }
+
+    /// Generates `types::RawEvent`, which only parses the parameters of an event
+    /// when it is decoded, and implements `types::EventMethod` for every event. Needs
+    /// the `raw_value` feature of `serde_json`.
+    pub fn raw_events(mut self, raw_events: bool) -> Self {
+        self.raw_events = raw_events;
+        self
+    }
+
+    pub(crate) fn has_raw_events(&self) -> bool {
+        self.raw_events || self.borrowed
+    }
+
+    /// Generates the types holding times, `Network.TimeSinceEpoch`,
+    /// `Network.MonotonicTime`, `Input.TimeSinceEpoch`, `Runtime.Timestamp` and
+    /// `Runtime.TimeDelta`, as wrappers of their `JsFloat` that convert to
+    /// `SystemTime` or `Duration` in the unit of the type, seconds or milliseconds.
+    pub fn time_types(mut self, time_types: bool) -> Self {
+        self.time_types = time_types;
+        self
+    }
+
+    /// Converts the [time types](Options::time_types) to the types of `time_crate`
+    /// too, which the generated code then depends on.
+    pub fn time_crate(mut self, time_crate: TimeCrate) -> Self {
+        if !self.time_crates.contains(&time_crate) {
+            self.time_crates.push(time_crate);
+        }
+        self
+    }
+
+    /// Makes the missing required fields of every struct an error, or gives them
+    /// their default value, [`MissingFields::Strict`] by default. Lenient mode derives
+    /// `Default` for every type that can have it, defaulting enums to their first
+    /// variant.
+    pub fn missing_fields(mut self, missing_fields: MissingFields) -> Self {
+        self.missing_fields = missing_fields;
+        self
+    }
+
+    pub(crate) fn lenient(&self) -> bool {
+        self.missing_fields == MissingFields::Lenient
+    }
+
+    /// Uses the Rust type at `rust_type`, e.g. `crate::headers::Headers`, instead of
+    /// generating the protocol type `Domain.Type`, at every field, array and alias
+    /// referring to it. Panics if `Domain.Type` doesn't exist.
+    ///
+    /// The path is used as-is from every domain module, so it should start with
+    /// `crate::` or a crate name. The type needs to implement `Serialize`,
+    /// `Deserialize`, `Debug`, `Clone` and `PartialEq`, and the types referring to
+    /// it don't get the other [checked derives](Options::derive). It never borrows
+    /// from the input.
+    pub fn override_type(mut self, path: impl Into<String>, rust_type: impl Into<String>) -> Self {
+        self.overrides.insert(path.into(), rust_type.into());
+        self
+    }
+
+    /// Generates the objects without properties at `path`, as [`derive`](Options::derive)
+    /// selects them, as `form`. Fields declared as inline objects are selected by the
+    /// type, command or event they belong to.
+    ///
+    /// The header types, whose id ends with `Headers` like `Network.Headers`, are
+    /// `HashMap<String, String>`s by default, with lookups ignoring case, and the
+    /// other objects `BTreeMap<String, Json>`s. The last matching `form` is used.
+    pub fn free_form(mut self, path: impl Into<String>, form: FreeForm) -> Self {
+        self.free_forms.push((path.into(), form));
+        self
+    }
+}
+
+/// Generates the module of every domain in `protocols`, in the order they are defined.
+///
+/// The modules refer to each other as `super::Domain` and to the shared types as
+/// `super::types`, so they are meant to be siblings of [`types_module`].
+pub fn domains(protocols: &[Protocol]) -> Vec<DomainModule> {
+    domains_with(protocols, &Options::default())
+}
+
+/// [`domains`] with `options`.
+pub fn domains_with(protocols: &[Protocol], options: &Options) -> Vec<DomainModule> {
+    compile_cdp_json(protocols, options)
+}
+
+/// Keeps the modules of `domains` and of the domains they depend on, panicking if
+/// one of `domains` isn't in `modules`. An empty `domains` keeps every module.
+pub fn select(modules: Vec<DomainModule>, domains: &[String]) -> Vec<DomainModule> {
+    if domains.is_empty() {
+        return modules;
+    }
+
+    let mut selected = BTreeSet::new();
+    let mut pending = domains.to_vec();
+    while let Some(domain) = pending.pop() {
+        let module = modules
+            .iter()
+            .find(|module| module.domain == domain)
+            .unwrap_or_else(|| panic!("unknown domain `{}`", domain));
+        if selected.insert(domain) {
+            pending.extend(module.dependencies.iter().cloned());
+        }
+    }
+
+    modules
+        .into_iter()
+        .filter(|module| selected.contains(&module.domain))
+        .collect()
+}
+
+/// The `cdp` module with every one of `modules` inline, as [`Layout::SingleFile`]
+/// generates it.
+///
+/// [`Layout::SingleFile`]: crate::Layout::SingleFile
+pub fn cdp_module(modules: &[DomainModule], options: &Options) -> TokenStream {
+    let types = types_module(modules.iter().flat_map(|module| &module.events), options);
+    let mods = modules.iter().map(|module| {
+        let name = &module.name;
+        let body = &module.body;
+        quote! {
+            pub mod #name {
+                #body
+            }
+        }
+    });
+
+    let methods = methods(modules.iter().flat_map(|module| &module.methods));
+
+    quote! {
+        #[allow(unused)]
+        #[allow(non_camel_case_types)]
+        #[allow(non_snake_case)]
+        pub mod cdp {
+            pub mod types {
+                #types
+            }
+
+            #methods
+
+            #(#mods)*
+        }
+    }
+}
+
+/// `METHODS`, the `MethodInfo` of every command, with `methods`, the
+/// [`DomainModule::methods`] of every domain. It goes next to `types`.
+pub fn methods<'a>(methods: impl IntoIterator<Item = &'a TokenStream>) -> TokenStream {
+    let methods = methods.into_iter();
+    quote! {
+        /// Every command of the protocol, in the order the domains define them.
+        pub static METHODS: &[types::MethodInfo] = &[
+            #(#methods)*
+        ];
+    }
+}
+
+/// Contents of `cdp::types`: the shared aliases, `Method`, the `Event` enum with
+/// `events`, the [`DomainModule::events`] of every domain, and `RawEvent` if
+/// [`Options::raw_events`] is set.
+pub fn types_module<'a>(
+    events: impl IntoIterator<Item = &'a TokenStream>,
+    options: &Options,
+) -> TokenStream {
+    let events = events.into_iter();
+    let cbor = crate::cbor::module();
+
+    let (return_object, event, raw_event) = if options.borrowed {
+        (
+            quote! { type ReturnObject<'de>: serde::Deserialize<'de> + std::fmt::Debug; },
+            borrowed_event(events),
+            quote! {
+                /// An event whose parameters are only parsed by [`RawEvent::decode`].
+                #[derive(Deserialize, Serialize, Debug, Clone)]
+                #[serde(rename_all = "camelCase")]
+                pub struct RawEvent<'a> {
+                    #[serde(borrow, deserialize_with = "borrow::cow_str")]
+                    pub method: std::borrow::Cow<'a, str>,
+                    #[serde(borrow, default, deserialize_with = "borrow::option_cow_str", skip_serializing_if = "Option::is_none")]
+                    pub session_id: Option<std::borrow::Cow<'a, str>>,
+                    #[serde(borrow, default = "no_params")]
+                    pub params: &'a RawValue,
+                }
+
+                fn no_params() -> &'static RawValue {
+                    serde_json::from_str("{}").unwrap()
+                }
+
+                impl<'a> RawEvent<'a> {
+                    /// The method of the event, e.g. `Page.frameNavigated`.
+                    pub fn kind(&self) -> &str {
+                        &self.method
+                    }
+
+                    /// Parses the parameters as the event `T`, borrowing from them.
+                    pub fn decode<T: EventMethod<'a>>(&self) -> serde_json::Result<T> {
+                        if self.method != T::NAME {
+                            return Err(serde::de::Error::custom(format_args!(
+                                "expected event `{}`, got `{}`",
+                                T::NAME,
+                                self.method
+                            )));
+                        }
+                        T::from_params(self.params)
+                    }
+                }
+
+                /// An event that [`RawEvent::decode`] can parse.
+                pub trait EventMethod<'a>: Sized {
+                    const NAME: &'static str;
+
+                    fn from_params(params: &'a RawValue) -> serde_json::Result<Self>;
+                }
+            },
+        )
+    } else {
+        (
+            quote! { type ReturnObject: serde::de::DeserializeOwned + std::fmt::Debug; },
+            quote! {
+                #[derive(Deserialize, Debug, Clone, PartialEq)]
+                #[serde(tag = "method")]
+                #[allow(clippy::large_enum_variant)]
+                pub enum Event {
+                    #(#events)*
+                }
+            },
+            quote! {
+                /// An event whose parameters are only parsed by [`RawEvent::decode`].
+                #[derive(Deserialize, Serialize, Debug, Clone)]
+                #[serde(rename_all = "camelCase")]
+                pub struct RawEvent {
+                    pub method: String,
+                    #[serde(default, skip_serializing_if = "Option::is_none")]
+                    pub session_id: Option<String>,
+                    #[serde(default = "no_params")]
+                    pub params: Box<RawValue>,
+                }
+
+                fn no_params() -> Box<RawValue> {
+                    RawValue::from_string("{}".to_owned()).unwrap()
+                }
+
+                impl RawEvent {
+                    /// The method of the event, e.g. `Page.frameNavigated`.
+                    pub fn kind(&self) -> &str {
+                        &self.method
+                    }
+
+                    /// Parses the parameters as the event `T`.
+                    pub fn decode<T: EventMethod>(&self) -> serde_json::Result<T> {
+                        if self.method != T::NAME {
+                            return Err(serde::de::Error::custom(format_args!(
+                                "expected event `{}`, got `{}`",
+                                T::NAME,
+                                self.method
+                            )));
+                        }
+                        T::from_params(&self.params)
+                    }
+                }
+
+                /// An event that [`RawEvent::decode`] can parse.
+                pub trait EventMethod: Sized {
+                    const NAME: &'static str;
+
+                    fn from_params(params: &RawValue) -> serde_json::Result<Self>;
+                }
+            },
+        )
+    };
+
+    let raw_event = if options.has_raw_events() {
+        quote! {
+            use serde_json::value::RawValue;
+
+            #raw_event
+        }
+    } else {
+        quote! {}
+    };
+
+    quote! {
+        use serde::{Deserialize, Serialize};
+        use std::fmt::Debug;
+
+        pub type JsFloat = f64;
+        pub type JsUInt = u32;
+
+        pub type WindowId = JsUInt;
+
+        pub type CallId = JsUInt;
+
+        /// Reads `null` as the empty map of a free-form object.
+        pub fn null_as_empty<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+        where
+            D: serde::Deserializer<'de>,
+            T: Deserialize<'de> + Default,
+        {
+            Option::<T>::deserialize(deserializer).map(Option::unwrap_or_default)
+        }
+
+
+        #[derive(Serialize, Debug)]
+        pub struct MethodCall<T>
+        where
+        T: Debug,
+        {
+            #[serde(rename = "method")]
+            method_name: &'static str,
+            pub id: CallId,
+            params: T,
+        }
+
+        impl<T> MethodCall<T>
+        where
+        T: Debug,
+        {
+            pub fn get_params(&self) -> &T {
+            &self.params
+            }
+        }
+
+        pub trait Method: Debug {
+        const NAME: &'static str;
+        /// Domain of the command, e.g. `Page`, empty for hand-written impls that
+        /// don't set it.
+        const DOMAIN: &'static str = "";
+        /// Whether the command or its domain is experimental.
+        const IS_EXPERIMENTAL: bool = false;
+        /// Whether the command is deprecated.
+        const IS_DEPRECATED: bool = false;
+
+        #return_object
+
+
+        fn to_method_call(self, call_id: CallId) -> MethodCall<Self>
+        where
+        Self: std::marker::Sized,
+        {
+            MethodCall {
+                id: call_id,
+                 params: self,
+                method_name: Self::NAME,
+                }
+        }
+
+        }
+
+        /// A command of [`METHODS`](super::METHODS), with the flags of its `Method` impl.
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        pub struct MethodInfo {
+            pub name: &'static str,
+            pub domain: &'static str,
+            pub experimental: bool,
+            pub deprecated: bool,
+            /// Protocol names of the parameters, e.g. `frameId`.
+            pub parameters: &'static [&'static str],
+        }
+
+        #event
+
+        #raw_event
+
+        #cbor
+    }
+}
+
+/// `Event<'a>` and the helpers the borrowing types deserialize with.
+///
+/// The events are entries like `"Page.frameNavigated" => PageFrameNavigated(...)`,
+/// possibly behind `#[cfg]`s, which a `macro_rules!` turns into the variants and
+/// the arms of the `Deserialize` impl. That impl parses each event from the raw
+/// message, since serde can't buffer the `RawValue`s in them for a tagged enum.
+fn borrowed_event<'a>(events: impl Iterator<Item = &'a TokenStream>) -> TokenStream {
+    quote! {
+        /// Deserializers for the `Cow<'a, str>`s in `Option`s, `Vec`s and type
+        /// aliases, which serde only borrows when they are written out.
+        pub mod borrow {
+            use serde::{de, Deserialize, Deserializer};
+            use std::borrow::Cow;
+            use std::fmt;
+
+            struct Str<'a>(Cow<'a, str>);
+
+            impl<'de: 'a, 'a> Deserialize<'de> for Str<'a> {
+                fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+                where
+                    D: Deserializer<'de>,
+                {
+                    struct Visitor;
+
+                    impl<'de> de::Visitor<'de> for Visitor {
+                        type Value = Cow<'de, str>;
+
+                        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                            formatter.write_str("a string")
+                        }
+
+                        fn visit_borrowed_str<E: de::Error>(self, value: &'de str) -> Result<Self::Value, E> {
+                            Ok(Cow::Borrowed(value))
+                        }
+
+                        fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+                            Ok(Cow::Owned(value.to_owned()))
+                        }
+
+                        fn visit_string<E: de::Error>(self, value: String) -> Result<Self::Value, E> {
+                            Ok(Cow::Owned(value))
+                        }
+                    }
+
+                    deserializer.deserialize_str(Visitor).map(Str)
+                }
+            }
+
+            pub fn cow_str<'de: 'a, 'a, D: Deserializer<'de>>(deserializer: D) -> Result<Cow<'a, str>, D::Error> {
+                Str::deserialize(deserializer).map(|value| value.0)
+            }
+
+            pub fn option_cow_str<'de: 'a, 'a, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Cow<'a, str>>, D::Error> {
+                Option::<Str>::deserialize(deserializer).map(|value| value.map(|value| value.0))
+            }
+
+            pub fn vec_cow_str<'de: 'a, 'a, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Cow<'a, str>>, D::Error> {
+                Vec::<Str>::deserialize(deserializer)
+                    .map(|values| values.into_iter().map(|value| value.0).collect())
+            }
+
+            pub fn option_vec_cow_str<'de: 'a, 'a, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Vec<Cow<'a, str>>>, D::Error> {
+                Option::<Vec<Str>>::deserialize(deserializer)
+                    .map(|values| values.map(|values| values.into_iter().map(|value| value.0).collect()))
+            }
+        }
+
+        macro_rules! events {
+            ($($(#[$attr:meta])* $method:literal => $variant:ident($event:ty),)*) => {
+                #[derive(Debug, Clone)]
+                #[allow(clippy::large_enum_variant)]
+                pub enum Event<'a> {
+                    $($(#[$attr])* $variant($event),)*
+                    // uses the lifetime when no event does, e.g. with their
+                    // domains turned off
+                    #[doc(hidden)]
+                    __Lifetime(std::marker::PhantomData<&'a ()>, std::convert::Infallible),
+                }
+
+                impl<'de: 'a, 'a> Deserialize<'de> for Event<'a> {
+                    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+                    where
+                        D: serde::Deserializer<'de>,
+                    {
+                        use serde::de::Error;
+
+                        #[derive(Deserialize)]
+                        struct Message<'m> {
+                            #[serde(borrow, deserialize_with = "borrow::cow_str")]
+                            method: std::borrow::Cow<'m, str>,
+                        }
+
+                        let message = <&'a serde_json::value::RawValue>::deserialize(deserializer)?;
+                        let Message { method } =
+                            serde_json::from_str(message.get()).map_err(D::Error::custom)?;
+
+                        let event: serde_json::Result<Self> = match &*method {
+                            $($(#[$attr])* $method => serde_json::from_str(message.get()).map(Event::$variant),)*
+                            _ => return Err(D::Error::custom(format_args!("unknown event `{}`", method))),
+                        };
+                        event.map_err(D::Error::custom)
+                    }
+                }
+            };
+        }
+
+        events! {
+            #(#events)*
+        }
+    }
+}
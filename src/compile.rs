@@ -1,7 +1,19 @@
-use std::{env, iter::FromIterator, path::Path};
-
-use convert_case::{Case, Casing};
-
+use std::{
+    collections::BTreeSet,
+    env,
+    iter::FromIterator,
+    path::{Path, PathBuf},
+};
+
+use crate::attributes::Attributes;
+use crate::cbor;
+use crate::codegen::Options;
+use crate::free_form::FreeFormObject;
+use crate::ident::{check_collisions, field_ident, ident, type_ident, variant_ident};
+use crate::lifetimes::Lifetimes;
+use crate::runtime;
+use crate::symbols::{Scope, SymbolTable};
+use crate::time;
 use crate::types::{Command, Event, Parameter, Protocol, TypeElement, TypeEnum};
 
 use proc_macro2::{Ident, Span, TokenStream};
@@ -13,9 +25,6 @@ include!(concat!(env!("OUT_DIR"), "/path.rs"));
 pub trait StringUtils {
     fn first_uppercase(&mut self);
     fn first_uppercased(self) -> Self;
-    fn replace_if<F>(self, from: &str, to: &str, predicate: F) -> String
-    where
-        F: FnOnce() -> bool;
 }
 
 impl StringUtils for String {
@@ -35,22 +44,11 @@ impl StringUtils for String {
             .map(|(i, f)| if i == 0 { f.to_ascii_uppercase() } else { f })
             .collect()
     }
-
-    fn replace_if<F>(self, from: &str, to: &str, predicate: F) -> String
-    where
-        F: FnOnce() -> bool,
-    {
-        if predicate() {
-            self.replace(from, to)
-        } else {
-            self.clone()
-        }
-    }
 }
 
-impl Into<Option<Ident>> for TypeEnum {
-    fn into(self) -> Option<Ident> {
-        match self {
+impl From<TypeEnum> for Option<Ident> {
+    fn from(type_enum: TypeEnum) -> Self {
+        match type_enum {
             TypeEnum::Boolean => Some(Ident::new("bool", Span::call_site())),
             TypeEnum::Integer => Some(Ident::new("JsUInt", Span::call_site())),
             TypeEnum::Number => Some(Ident::new("JsFloat", Span::call_site())),
@@ -61,93 +59,55 @@ impl Into<Option<Ident>> for TypeEnum {
     }
 }
 
-enum PropertyType<'a> {
-    Param(&'a Parameter),
-    Element(&'a TypeElement),
+/// What the code of every domain is generated with.
+pub struct Context<'a> {
+    protocols: &'a [Protocol],
+    symbols: &'a SymbolTable<'a>,
+    attributes: &'a Attributes<'a>,
+    lifetimes: &'a Lifetimes<'a>,
+    options: &'a Options,
 }
 
-fn add_dependency(dependencies: &mut Vec<TokenStream>, dependency: &Ident) {
-    let present = dependencies
-        .iter()
-        .any(|v| v.to_string().contains(&dependency.to_string()));
-
-    if !present {
-        dependencies.push(quote! {
-            use super::#dependency;
-        });
-    }
+/// The generated struct a list of fields belongs to.
+struct Owner<'a> {
+    domain: &'a str,
+    scope: Scope,
+    /// Protocol name of the type, command or event the fields belong to.
+    name: &'a str,
+    /// Id of the type being generated, so that the fields closing a cycle get boxed.
+    type_id: Option<&'a str>,
 }
 
-fn tokenize_enum(enum_vec: &Vec<String>, enum_name: String) -> (Ident, TokenStream) {
+/// The enum `enum_name` of the strings `enum_vec`, defaulting to the first one if
+/// `default` is set.
+fn tokenize_enum(
+    enum_vec: &[String],
+    enum_name: String,
+    attributes: TokenStream,
+    default: bool,
+) -> (Ident, TokenStream) {
     let enum_tokens: Vec<TokenStream> = enum_vec
         .iter()
-        .map(|e| {
-            let enum_type = if e.contains("-") {
-                let enum_type = e
-                    .split("-")
-                    .map(|s| {
-                        let mut upper = s.to_string();
-                        upper.first_uppercase();
-                        upper
-                    })
-                    .collect::<Vec<String>>()
-                    .join("");
-
-                Ident::new(&enum_type, Span::call_site())
+        .enumerate()
+        .map(|(i, e)| {
+            let enum_type = variant_ident(e);
+            let default = if default && i == 0 {
+                quote! { #[default] }
             } else {
-                let e = e.to_case(Case::Pascal);
-                // We can't escape Self (e.g., r#Self) to be a raw identify.
-                if e == "Self" {
-                    Ident::new(&String::from("CdpSelf"), Span::call_site())
-                } else {
-                    Ident::new(&e.to_case(Case::Pascal), Span::call_site())
-                }
+                quote! {}
             };
             quote! {
                 // tend to use serde renaming to keep compatities
                 #[serde(rename = #e)]
+                #default
                 #enum_type,
             }
         })
         .collect();
-    let enum_name = Ident::new(&enum_name, Span::call_site());
-
-    /*
-    // FIXME: Some special case not covered by rename-all
-    let vec: Vec<&String> = enum_vec
-        .iter()
-        .filter(|v| {
-            let c = v.chars().next().unwrap();
-            if c.is_uppercase() {
-                true
-            } else if v.contains("-") {
-                true
-            } else {
-                false
-            }
-        })
-        .collect();
-
-    let mut rename = quote! {
-        #[serde(rename_all = "camelCase")]
-    };
-    if vec.len() > 0 {
-        let v = vec[0];
-        let c = v.chars().next().unwrap();
-        if c.is_uppercase() {
-            rename = quote! {
-                #[serde(rename_all = "PascalCase")]
-            }
-        } else if v.contains("-") {
-            rename = quote! {
-                #[serde(rename_all = "kebab-case")]
-            }
-        }
-    } */
+    let enum_name = type_ident(&enum_name);
 
     let typ_enum = quote! {
-        #[derive(Deserialize,Serialize, Debug,Clone,PartialEq)]
-        // #rename
+        #attributes
         pub enum #enum_name {
             #(#enum_tokens)*
         }
@@ -155,1146 +115,578 @@ fn tokenize_enum(enum_vec: &Vec<String>, enum_name: String) -> (Ident, TokenStre
     (enum_name, typ_enum)
 }
 
+/// Rust type of `parameter`. Arrays of arrays have no Rust type and are skipped.
+fn field_type(parameter: &Parameter, owner: &Owner, context: &Context) -> Option<TokenStream> {
+    let lifetimes = context.lifetimes;
+
+    if let Some(p_ref) = &parameter.parameter_ref {
+        let path = context.symbols.path(owner.domain, p_ref, owner.scope);
+        let generics = lifetimes.generics(lifetimes.of_ref(owner.domain, p_ref));
+
+        let boxed = owner.type_id.is_some_and(|type_id| {
+            context
+                .symbols
+                .is_boxed(owner.domain, type_id, &parameter.name)
+        });
+        if boxed {
+            return Some(quote! { Box<#path #generics> });
+        }
+        return Some(quote! { #path #generics });
+    }
+
+    match parameter.parameter_type? {
+        TypeEnum::Array => {
+            let items = parameter.items.as_ref().unwrap();
+
+            if let Some(p_ref) = &items.items_ref {
+                let path = context.symbols.path(owner.domain, p_ref, owner.scope);
+                let generics = lifetimes.generics(lifetimes.of_ref(owner.domain, p_ref));
+                Some(quote! { Vec<#path #generics> })
+            } else if let Some(TypeEnum::Object) = items.items_type {
+                let object = FreeFormObject::inline(context.options, owner.domain, owner.name);
+                let typ = object.rust_type(lifetimes);
+                Some(quote! { Vec<#typ> })
+            } else {
+                let typ = lifetimes.primitive(items.items_type.unwrap());
+                typ.map(|typ| quote! { Vec<#typ> })
+            }
+        }
+        TypeEnum::Object => {
+            let object = FreeFormObject::inline(context.options, owner.domain, owner.name);
+            Some(object.rust_type(lifetimes))
+        }
+        TypeEnum::String if parameter.parameter_enum.is_some() => {
+            let inline_enum = context
+                .symbols
+                .inline_enum(owner.domain, owner.name, parameter)?;
+            let enum_name = ident(&inline_enum.name);
+
+            match owner.scope {
+                Scope::Domain => Some(quote! { #enum_name }),
+                Scope::Events => Some(quote! { super::#enum_name }),
+            }
+        }
+        type_type => lifetimes.primitive(type_type),
+    }
+}
+
+fn get_fields(parameters: &[Parameter], owner: &Owner, context: &Context) -> Vec<TokenStream> {
+    parameters
+        .iter()
+        .filter_map(|parameter| {
+            let typ = field_type(parameter, owner, context)?;
+
+            let parameter_name = &parameter.name;
+            let name = field_ident(parameter_name);
+            let borrow = context.lifetimes.field_attributes(owner.domain, parameter);
+            let binary = cbor::field_attributes(parameter);
+            let optional = parameter.optional.unwrap_or(false);
+            let null = match parameter.parameter_type {
+                Some(TypeEnum::Object) if !optional => {
+                    FreeFormObject::inline(context.options, owner.domain, owner.name)
+                        .field_attributes()
+                }
+                _ => quote! {},
+            };
+            let default = if optional {
+                !borrow.is_empty()
+            } else {
+                context
+                    .attributes
+                    .has_default(owner.domain, owner.name, parameter)
+            };
+            let default = if default {
+                quote! { #[serde(default)] }
+            } else {
+                quote! {}
+            };
+
+            if optional {
+                Some(quote! {
+                    #[serde(skip_serializing_if="Option::is_none")]
+                    #default
+                    #borrow
+                    #binary
+                    #[serde(rename = #parameter_name)]
+                    pub #name: Option<#typ>,
+                })
+            } else {
+                Some(quote! {
+                    #default
+                    #borrow
+                    #null
+                    #binary
+                    #[serde(rename = #parameter_name)]
+                    pub #name: #typ,
+                })
+            }
+        })
+        .collect()
+}
+
 fn get_types(
-    type_type: TypeEnum,
-    property_type: PropertyType,
-    type_element: Option<&TypeElement>,
+    domain: &str,
+    type_element: &TypeElement,
+    context: &Context,
     types: &mut Vec<TokenStream>,
     enums: &mut Vec<TokenStream>,
     objects: &mut Vec<TokenStream>,
-    object: &mut Vec<TokenStream>,
-    dependencies: &mut Vec<TokenStream>,
-    previous_type: Option<Ident>,
 ) {
-    match property_type {
-        PropertyType::Param(param) => {
-            // || param.name.starts_with("type")
-            let param_name = &param.name;
-            let name = Ident::new(
-                &String::from(
-                    param_name
-                        .to_case(Case::Snake)
-                        .replace_if("type", "Type", || param.name.starts_with("type")),
-                ),
-                Span::call_site(),
-            );
+    // the Rust type replacing it is referred to instead
+    if context.symbols.is_overridden(domain, &type_element.id) {
+        return;
+    }
 
-            match type_type {
-                TypeEnum::Array => {
-                    let items = param.items.as_ref().unwrap();
-
-                    if let Some(p_ref) = &items.items_ref {
-                        if let Some(p_type) = previous_type {
-                            if let Some(_) = param.optional {
-                                let v = quote! {
-                                    #[serde(skip_serializing_if="Option::is_none")]
-                                    #[serde(rename = #param_name)]
-                                    pub #name: Option<#p_type<#p_ref>>,
-                                };
-                                object.push(v);
-                            } else {
-                                let v = quote! {
-                                    #[serde(rename = #param_name)]
-                                    pub #name: #p_type<#p_ref>,
-                                };
-                                object.push(v);
-                            }
-                        } else {
-                            if p_ref.contains(".") {
-                                let dep = &p_ref
-                                    .split(".")
-                                    .map(|v| Ident::new(v, Span::call_site()))
-                                    .collect::<Vec<Ident>>()[0];
-
-                                let v: Vec<&TokenStream> = dependencies
-                                    .iter()
-                                    .filter(|v| {
-                                        let r = p_ref.split(".").collect::<Vec<&str>>()[0];
-                                        v.to_string().contains(r)
-                                    })
-                                    .collect();
-
-                                if v.len() <= 0 {
-                                    dependencies.push(quote! {
-                                        use super::#dep;
-                                    });
-                                }
-                            }
-
-                            let dep = p_ref
-                                .split(".")
-                                .map(|v| Ident::new(v, Span::call_site()))
-                                .collect::<Vec<Ident>>();
-
-                            if let Some(_) = param.optional {
-                                let v = quote! {
-                                    #[serde(skip_serializing_if="Option::is_none")]
-                                    #[serde(rename = #param_name)]
-                                    pub #name: Option<Vec<#(#dep)::*>>,
-                                };
-                                object.push(v);
-                            } else {
-                                let v = quote! {
-                                    #[serde(rename = #param_name)]
-                                    pub #name: Vec<#(#dep)::*>,
-                                };
-                                object.push(v);
-                            }
-                        }
-                    } else {
-                        let p_type = items.items_type.as_ref().unwrap().clone();
-
-                        get_types(
-                            p_type,
-                            PropertyType::Param(param),
-                            type_element,
-                            types,
-                            enums,
-                            objects,
-                            object,
-                            dependencies,
-                            Some(Ident::new("Vec", Span::call_site())),
-                        );
-                    }
+    let name = type_ident(&type_element.id);
+    let lifetimes = context.lifetimes;
+    let generics = lifetimes.generics(lifetimes.of_ref(domain, &type_element.id));
+
+    match type_element.type_type {
+        TypeEnum::Array => {
+            let items = type_element.items.as_ref().unwrap();
+
+            let item_type = match &items.items_ref {
+                Some(p_ref) => {
+                    let path = context.symbols.path(domain, p_ref, Scope::Domain);
+                    let item_generics = lifetimes.generics(lifetimes.of_ref(domain, p_ref));
+                    quote! { #path #item_generics }
                 }
-                TypeEnum::String => {
-                    if let Some(enum_vec) = &param.parameter_enum {
-                        let (enum_name, typ_enum) = tokenize_enum(
-                            enum_vec,
-                            (type_element.unwrap().id.clone()
-                                + &name.to_string().to_case(Case::Pascal))
-                                .to_case(Case::Pascal),
-                        );
-
-                        if let Some(p_type) = previous_type {
-                            if let Some(_) = param.optional {
-                                let v = quote! {
-                                    #[serde(skip_serializing_if="Option::is_none")]
-                                    #[serde(rename = #param_name)]
-                                    pub #name: Option<#p_type<#enum_name>>,
-                                };
-                                object.push(v);
-                            } else {
-                                let v = quote! {
-                                    #[serde(rename = #param_name)]
-                                    pub #name: #p_type<#enum_name>,
-                                };
-                                object.push(v);
-                            }
-                        } else {
-                            if let Some(_) = param.optional {
-                                let v = quote! {
-                                    #[serde(skip_serializing_if="Option::is_none")]
-                                    #[serde(rename = #param_name)]
-                                    pub #name: Option<#enum_name>,
-                                };
-                                object.push(v);
-                            } else {
-                                let v = quote! {
-                                    #[serde(rename = #param_name)]
-                                    pub #name: #enum_name,
-                                };
-                                object.push(v);
-                            }
-                        }
-                        enums.push(typ_enum);
-                    } else {
-                        if let Some(p_type) = previous_type {
-                            if let Some(_) = param.optional {
-                                let v = quote! {
-                                    #[serde(skip_serializing_if="Option::is_none")]
-                                    #[serde(default)]
-                                    #[serde(rename = #param_name)]
-                                    pub #name: Option<#p_type<String>>,
-                                };
-                                object.push(v);
-                            } else {
-                                let v = quote! {
-                                    #[serde(default)]
-                                    #[serde(rename = #param_name)]
-                                    pub #name: #p_type<String>,
-                                };
-                                object.push(v);
-                            }
-                        } else {
-                            if let Some(_) = param.optional {
-                                let v = quote! {
-                                    #[serde(skip_serializing_if="Option::is_none")]
-                                    #[serde(default)]
-                                    #[serde(rename = #param_name)]
-                                    pub #name: Option<String>,
-                                };
-                                object.push(v);
-                            } else {
-                                let v = quote! {
-                                    #[serde(default)]
-                                    #[serde(rename = #param_name)]
-                                    pub #name: String,
-                                };
-                                object.push(v);
-                            }
-                        }
+                None => match items.items_type.unwrap() {
+                    TypeEnum::Object => {
+                        FreeFormObject::inline(context.options, domain, &type_element.id)
+                            .rust_type(lifetimes)
                     }
-                }
-                _ => {
-                    let type_type: Option<Ident> = type_type.into();
-
-                    if let Some(typ) = type_type {
-                        if let Some(p_type) = previous_type {
-                            if let Some(_) = param.optional {
-                                let v = quote! {
-                                    #[serde(skip_serializing_if="Option::is_none")]
-                                    #[serde(default)]
-                                    #[serde(rename = #param_name)]
-                                    pub #name: Option<#p_type<#typ>>,
-                                };
-                                object.push(v);
-                            } else {
-                                let v = quote! {
-                                    #[serde(default)]
-                                    #[serde(rename = #param_name)]
-                                    pub #name: #p_type<#typ>,
-                                };
-                                object.push(v);
-                            }
-                        } else {
-                            if let Some(_) = param.optional {
-                                let v = quote! {
-                                    #[serde(skip_serializing_if="Option::is_none")]
-                                    #[serde(default)]
-                                    #[serde(rename = #param_name)]
-                                    pub #name: Option<#typ>,
-                                };
-                                object.push(v);
-                            } else {
-                                let v = quote! {
-                                    #[serde(default)]
-                                    #[serde(rename = #param_name)]
-                                    pub #name: #typ,
-                                };
-                                object.push(v);
-                            }
-                        }
+                    items_type => {
+                        let typ = lifetimes.primitive(items_type);
+                        quote! { #typ }
                     }
-                }
-            }
+                },
+            };
+
+            types.push(quote! {
+                pub type #name #generics = Vec<#item_type>;
+            });
         }
-        PropertyType::Element(typ_element) => {
-            let element_id = &typ_element.id;
-            let name = Ident::new(element_id, Span::call_site());
-
-            match type_type {
-                TypeEnum::Array => {
-                    let items = typ_element.items.as_ref().unwrap();
-
-                    if let Some(p_ref) = &items.items_ref {
-                        let p_ref = Ident::new(&p_ref, Span::call_site());
-                        let v = quote! {
-                            pub type #name = Vec<#p_ref>;
-                        };
-                        types.push(v);
-                    } else {
-                        let p_type = items.items_type.as_ref().unwrap().clone();
-
-                        get_types(
-                            p_type,
-                            PropertyType::Element(typ_element),
-                            Some(typ_element),
-                            types,
-                            enums,
-                            objects,
-                            object,
-                            dependencies,
-                            Some(Ident::new("Vec", Span::call_site())),
-                        );
-                    }
-                }
-                TypeEnum::Object => {
-                    if let Some(properties) = typ_element.properties.as_deref() {
-                        for property in properties {
-                            match &property.parameter_type {
-                                Some(p) => get_types(
-                                    p.clone(),
-                                    PropertyType::Param(property),
-                                    Some(typ_element),
-                                    types,
-                                    enums,
-                                    objects,
-                                    object,
-                                    dependencies,
-                                    None,
-                                ),
-                                None => {
-                                    let property_name = &property.name;
-                                    let p_name = Ident::new(
-                                        &property_name.to_case(Case::Snake).replace("type", "Type"),
-                                        Span::call_site(),
-                                    );
-
-                                    let p_ref = property.parameter_ref.as_ref().unwrap().clone();
-
-                                    if p_ref.contains(".") {
-                                        let dep = &p_ref
-                                            .split(".")
-                                            .map(|v| Ident::new(v, Span::call_site()))
-                                            .collect::<Vec<Ident>>()[0];
-
-                                        let v: Vec<&TokenStream> = dependencies
-                                            .iter()
-                                            .filter(|v| {
-                                                let r = p_ref.split(".").collect::<Vec<&str>>()[0];
-                                                v.to_string().contains(r)
-                                            })
-                                            .collect();
-
-                                        if v.len() <= 0 {
-                                            dependencies.push(quote! {
-                                                use super::#dep;
-                                            });
-                                        }
-                                    }
-
-                                    if p_ref == typ_element.id {
-                                        let p_ref = Ident::new(&p_ref, Span::call_site());
-                                        if let Some(_) = property.optional {
-                                            let v = quote! {
-                                                #[serde(skip_serializing_if="Option::is_none")]
-                                                #[serde(rename = #property_name)]
-                                                pub #p_name: Option<Box<#p_ref>>,
-                                            };
-                                            object.push(v);
-                                        } else {
-                                            let v = quote! {
-                                                #[serde(rename = #property_name)]
-                                                pub #p_name: Box<#p_ref>,
-                                            };
-                                            object.push(v);
-                                        }
-                                    } else {
-                                        let dep = p_ref
-                                            .split(".")
-                                            .map(|v| Ident::new(v, Span::call_site()))
-                                            .collect::<Vec<Ident>>();
-
-                                        if let Some(_) = property.optional {
-                                            let v = quote! {
-                                                #[serde(skip_serializing_if="Option::is_none")]
-                                                #[serde(rename = #property_name)]
-                                                pub #p_name: Option<#(#dep)::*>,
-                                            };
-                                            object.push(v);
-                                        } else {
-                                            let v = quote! {
-                                                #[serde(rename = #property_name)]
-                                                pub #p_name: #(#dep)::*,
-                                            };
-                                            object.push(v);
-                                        }
-                                    }
-                                }
-                            };
-                        }
-                    }
-                    if object.len() > 0 {
-                        objects.push(quote! {
-                                #[derive(Deserialize,Serialize, Debug,Clone,PartialEq)]
-                                // #[serde(rename_all = "camelCase")]
-                                pub struct #name {
-                                    #(#object)*
-                                }
-                        });
-                    } else {
-                        objects.push(quote! {
-                                #[derive(Deserialize,Serialize, Debug,Clone,PartialEq)]
-                                #[serde(rename_all = "camelCase")]
-                                pub struct #name(pub Option<serde_json::Value>);
-                        });
-                    }
-                }
-                TypeEnum::String => {
-                    if let Some(enum_vec) = typ_element.type_enum.clone() {
-                        let (_, typ_enum) = tokenize_enum(&enum_vec, name.to_string());
-                        enums.push(typ_enum);
-                    } else {
-                        if let Some(p_type) = previous_type {
-                            let v = quote! {
-                                pub type #name = #p_type<String>;
-                            };
-
-                            types.push(v);
-                        } else {
-                            let v = quote! {
-                                pub type #name = String;
-                            };
-
-                            types.push(v);
-                        }
-                    }
-                }
-                _ => {
-                    let type_type: Option<Ident> = type_type.into();
-
-                    if let Some(typ) = type_type {
-                        if let Some(p_type) = previous_type {
-                            let v = quote! {
-                                pub type #name = #p_type<#typ>;
-                            };
-                            types.push(v);
-                        } else {
-                            let v = quote! {
-                                pub type #name = #typ;
-                            };
-                            types.push(v);
+        TypeEnum::Object => {
+            let owner = Owner {
+                domain,
+                scope: Scope::Domain,
+                name: &type_element.id,
+                type_id: Some(&type_element.id),
+            };
+            let properties = type_element.properties.as_deref().unwrap_or_default();
+            let object = get_fields(properties, &owner, context);
+
+            if !object.is_empty() {
+                let attributes = context
+                    .attributes
+                    .of_fields(domain, &type_element.id, properties);
+                objects.push(quote! {
+                        #attributes
+                        pub struct #name #generics {
+                            #(#object)*
                         }
-                    }
-                }
+                });
+            } else {
+                let object = FreeFormObject::of_type(context.options, domain, &type_element.id);
+                let attributes = context
+                    .attributes
+                    .of_object(domain, &type_element.id, &object);
+                let typ = object.rust_type(lifetimes);
+                let borrow = if object.borrows(lifetimes) {
+                    quote! { #[serde(borrow)] }
+                } else {
+                    quote! {}
+                };
+                let null = object.field_attributes();
+                let helpers = object.helpers(&name);
+                objects.push(quote! {
+                        #attributes
+                        pub struct #name #generics(#borrow #null pub #typ);
+
+                        #helpers
+                });
             }
         }
-    };
+        TypeEnum::String => {
+            if let Some(enum_vec) = &type_element.type_enum {
+                let (_, typ_enum) = tokenize_enum(
+                    enum_vec,
+                    type_element.id.clone(),
+                    context.attributes.of_enum(domain, &type_element.id),
+                    context.options.lenient(),
+                );
+                enums.push(typ_enum);
+            } else {
+                let typ = lifetimes.primitive(TypeEnum::String);
+                types.push(quote! {
+                    pub type #name #generics = #typ;
+                });
+            }
+        }
+        type_type => {
+            if let Some(wrapper) =
+                time::wrapper(domain, type_element, context.attributes, context.options)
+            {
+                types.push(wrapper);
+            } else if let Some(typ) = lifetimes.primitive(type_type) {
+                types.push(quote! {
+                    pub type #name #generics = #typ;
+                });
+            }
+        }
+    }
 }
 
 pub fn get_commands(
-    commands: &Vec<Command>,
-    dependencies: &mut Vec<TokenStream>,
+    domain: &str,
+    commands: &[Command],
+    context: &Context,
     command_objects: &mut Vec<TokenStream>,
     parameter_objects: &mut Vec<TokenStream>,
-    enums: &mut Vec<TokenStream>,
 ) {
     for command in commands {
+        // redirected commands are emitted as wrappers by `get_redirect`
+        if redirect_target(command, context.symbols).is_some() {
+            continue;
+        }
+
+        let owner = Owner {
+            domain,
+            scope: Scope::Domain,
+            name: &command.name,
+            type_id: None,
+        };
+
         let mut name = command.name.clone();
         name.first_uppercase();
         name.push_str("ReturnObject");
-        let name = Ident::new(&name, Span::call_site());
+        let name = type_ident(&name);
+        let generics = context
+            .lifetimes
+            .generics(context.lifetimes.of_returns(domain, command));
         if let Some(returns) = &command.returns {
-            let mut command_object: Vec<TokenStream> = Vec::new();
-
-            for return_type in returns {
-                if let Some(param_type) = return_type.parameter_type {
-                    let ret_type_name = &return_type.name;
-                    let name = Ident::new(
-                        &ret_type_name.clone().to_case(Case::Snake),
-                        Span::call_site(),
-                    );
-
-                    match param_type {
-                        TypeEnum::Array => {
-                            let items = return_type.items.as_ref().unwrap();
-
-                            if let Some(ref_type) = items.items_ref.clone() {
-                                if ref_type.contains(".") {
-                                    let dep = ref_type
-                                        .split(".")
-                                        .map(|v| Ident::new(v, Span::call_site()))
-                                        .collect::<Vec<Ident>>();
-
-                                    add_dependency(dependencies, &dep[0]);
-                                    if let Some(_) = return_type.optional {
-                                        let v = quote! {
-                                            #[serde(skip_serializing_if="Option::is_none")]
-                                            #[serde(rename = #ret_type_name)]
-                                            pub #name: Option<#(#dep)::*>,
-                                        };
-
-                                        command_object.push(v);
-                                    } else {
-                                        let v = quote! {
-                                            #[serde(rename = #ret_type_name)]
-                                            pub #name: #(#dep)::*,
-                                        };
-
-                                        command_object.push(v);
-                                    }
-                                } else {
-                                    let ref_type = Ident::new(&ref_type, Span::call_site());
-
-                                    if let Some(_) = return_type.optional {
-                                        let v = quote! {
-                                            #[serde(skip_serializing_if="Option::is_none")]
-                                            #[serde(rename = #ret_type_name)]
-                                            pub #name: Option<Vec<#ref_type>>,
-                                        };
-                                        command_object.push(v);
-                                    } else {
-                                        let v = quote! {
-                                            #[serde(rename = #ret_type_name)]
-                                            pub #name: Vec<#ref_type>,
-                                        };
-                                        command_object.push(v);
-                                    }
-                                }
-                            } else {
-                                let type_type: Option<Ident> = items.items_type.unwrap().into();
-
-                                if let Some(typ) = type_type {
-                                    if let Some(_) = return_type.optional {
-                                        let v = quote! {
-                                            #[serde(skip_serializing_if="Option::is_none")]
-                                            #[serde(rename = #ret_type_name)]
-                                            pub #name: Option<Vec<#typ>>,
-                                        };
-
-                                        command_object.push(v);
-                                    } else {
-                                        let v = quote! {
-                                            #[serde(rename = #ret_type_name)]
-                                            pub #name: Vec<#typ>,
-                                        };
-
-                                        command_object.push(v);
-                                    }
-                                }
-                            }
-                        }
-                        TypeEnum::String => {
-                            if let Some(enum_vec) = &return_type.parameter_enum {
-                                let (enum_name, typ_enum) = tokenize_enum(
-                                    enum_vec,
-                                    name.to_string().to_case(Case::Pascal) + "Option",
-                                );
-                                enums.push(typ_enum);
-
-                                if let Some(_) = return_type.optional {
-                                    let v = quote! {
-                                        #[serde(skip_serializing_if="Option::is_none")]
-                                        #[serde(rename = #ret_type_name)]
-                                        pub #name: Option<#enum_name>,
-                                    };
-                                    command_object.push(v);
-                                } else {
-                                    let v = quote! {
-                                        #[serde(rename = #ret_type_name)]
-                                        pub #name: #enum_name,
-                                    };
-                                    command_object.push(v);
-                                }
-                            } else {
-                                if let Some(_) = return_type.optional {
-                                    let v = quote! {
-                                        #[serde(skip_serializing_if="Option::is_none")]
-                                        #[serde(default)]
-                                        #[serde(rename = #ret_type_name)]
-                                        pub #name: Option<String>,
-                                    };
-
-                                    command_object.push(v);
-                                } else {
-                                    let v = quote! {
-                                        #[serde(default)]
-                                        #[serde(rename = #ret_type_name)]
-                                        pub #name: String,
-                                    };
-
-                                    command_object.push(v);
-                                }
-                            }
-                        }
-                        _ => {
-                            let type_type: Option<Ident> = param_type.into();
-
-                            if let Some(typ) = type_type {
-                                if let Some(_) = return_type.optional {
-                                    let v = quote! {
-                                        #[serde(skip_serializing_if="Option::is_none")]
-                                        #[serde(default)]
-                                        #[serde(rename = #ret_type_name)]
-                                        pub #name: Option<#typ>,
-                                    };
-
-                                    command_object.push(v);
-                                } else {
-                                    let v = quote! {
-                                        #[serde(default)]
-                                        #[serde(rename = #ret_type_name)]
-                                        pub #name: #typ,
-                                    };
-
-                                    command_object.push(v);
-                                }
-                            }
-                        }
-                    }
-                } else {
-                    let p_ref = &return_type.parameter_ref.clone().unwrap();
-
-                    let ret_type_name = &return_type.name;
-
-                    let ret_type =
-                        Ident::new(&ret_type_name.to_case(Case::Snake), Span::call_site());
-
-                    if p_ref.contains(".") {
-                        let dep = p_ref
-                            .split(".")
-                            .map(|v| Ident::new(v, Span::call_site()))
-                            .collect::<Vec<Ident>>();
-
-                        if let Some(_) = return_type.optional {
-                            let v = quote! {
-                                #[serde(skip_serializing_if="Option::is_none")]
-                                #[serde(rename = #ret_type_name)]
-                                pub #ret_type: Option<#(#dep)::*>,
-                            };
-                            command_object.push(v);
-                        } else {
-                            let v = quote! {
-                                #[serde(rename = #ret_type_name)]
-                                pub #ret_type: #(#dep)::*,
-                            };
-                            command_object.push(v);
-                        }
-                    } else {
-                        let p_ref = Ident::new(&p_ref, Span::call_site());
-
-                        if let Some(_) = return_type.optional {
-                            let v = quote! {
-                                #[serde(skip_serializing_if="Option::is_none")]
-                                #[serde(rename = #ret_type_name)]
-                                pub #ret_type: Option<#p_ref>,
-                            };
-
-                            command_object.push(v);
-                        } else {
-                            let v = quote! {
-                                #[serde(rename = #ret_type_name)]
-                                pub #ret_type: #p_ref,
-                            };
-
-                            command_object.push(v);
-                        }
-                    }
-                }
-            }
+            let command_object = get_fields(returns, &owner, context);
+
+            let attributes = context.attributes.of_fields(domain, &command.name, returns);
             command_objects.push(quote! {
-                #[derive(Deserialize,Serialize, Debug,Clone,PartialEq)]
-                // #[serde(rename_all = "camelCase")]
-                pub struct #name {
+                #attributes
+                pub struct #name #generics {
                     #(#command_object)*
                 }
             });
         } else {
+            let attributes = context.attributes.of_fields(domain, &command.name, &[]);
             command_objects.push(quote! {
-                #[derive(Deserialize,Serialize, Debug,Clone,PartialEq)]
+                #attributes
                 #[serde(rename_all = "camelCase")]
                 pub struct #name {}
             });
         }
 
-        get_parameters(command, dependencies, parameter_objects, enums);
+        get_parameters(domain, command, context, parameter_objects);
     }
 }
 
 pub fn get_parameters(
+    domain: &str,
     command: &Command,
-    dependencies: &mut Vec<TokenStream>,
+    context: &Context,
     parameter_objects: &mut Vec<TokenStream>,
-    enums: &mut Vec<TokenStream>,
 ) {
     let mut name = command.name.clone();
     name.first_uppercase();
-    let name = Ident::new(&name, Span::call_site());
+    let name = type_ident(&name);
+    let generics = context
+        .lifetimes
+        .generics(context.lifetimes.of_parameters(domain, command));
+
+    // redirects without a same-named command in the target domain are kept as-is
+    let doc = match &command.redirect {
+        Some(redirect) => {
+            let doc = format!("Redirected to the `{}` domain.", redirect);
+            quote! { #[doc = #doc] }
+        }
+        None => quote! {},
+    };
 
     if let Some(parameters) = command.parameters.as_deref() {
-        let mut parameter_object: Vec<TokenStream> = Vec::new();
-        for parameter in parameters {
-            let parameter_name = &parameter.name;
-            let p_name = Ident::new(
-                &parameter_name
-                    .to_case(Case::Snake)
-                    .replace("type", "Type")
-                    .replace("override", "Override"),
-                Span::call_site(),
-            );
-
-            if let Some(param_type) = parameter.parameter_type {
-                match param_type {
-                    TypeEnum::Array => {
-                        let items = parameter.items.as_ref().unwrap();
-
-                        if let Some(ref_type) = items.items_ref.clone() {
-                            if ref_type.contains(".") {
-                                let dep = ref_type
-                                    .split(".")
-                                    .map(|v| Ident::new(v, Span::call_site()))
-                                    .collect::<Vec<Ident>>();
-
-                                add_dependency(dependencies, &dep[0]);
-
-                                if let Some(_) = parameter.optional {
-                                    let v = quote! {
-                                        #[serde(skip_serializing_if="Option::is_none")]
-                                        #[serde(rename = #parameter_name)]
-                                        pub #p_name: Option<#(#dep)::*>,
-                                    };
-                                    parameter_object.push(v);
-                                } else {
-                                    let v = quote! {
-                                        #[serde(rename = #parameter_name)]
-                                        pub #p_name: #(#dep)::*,
-                                    };
-                                    parameter_object.push(v);
-                                }
-                            } else {
-                                let ref_type = Ident::new(&ref_type, Span::call_site());
-
-                                if let Some(_) = parameter.optional {
-                                    let v = quote! {
-                                        #[serde(skip_serializing_if="Option::is_none")]
-                                        #[serde(rename = #parameter_name)]
-                                        pub #p_name: Option<Vec<#ref_type>>,
-                                    };
-                                    parameter_object.push(v);
-                                } else {
-                                    let v = quote! {
-                                        #[serde(rename = #parameter_name)]
-                                        pub #p_name: Vec<#ref_type>,
-                                    };
-                                    parameter_object.push(v);
-                                }
-                            }
-                        } else {
-                            let type_type: Option<Ident> = items.items_type.unwrap().into();
-
-                            if let Some(typ) = type_type {
-                                if let Some(_) = parameter.optional {
-                                    let v = quote! {
-                                        #[serde(skip_serializing_if="Option::is_none")]
-                                        #[serde(default)]
-                                        #[serde(rename = #parameter_name)]
-                                        pub #p_name: Option<Vec<#typ>>,
-                                    };
-
-                                    parameter_object.push(v);
-                                } else {
-                                    let v = quote! {
-                                        #[serde(default)]
-                                        #[serde(rename = #parameter_name)]
-                                        pub #p_name: Vec<#typ>,
-                                    };
-
-                                    parameter_object.push(v);
-                                }
-                            }
-                        }
-                    }
-                    TypeEnum::String => {
-                        if let Some(enum_vec) = &parameter.parameter_enum {
-                            let (enum_name, typ_enum) = tokenize_enum(
-                                enum_vec,
-                                name.to_string()
-                                    + &p_name.to_string().first_uppercased()
-                                    + "Option",
-                            );
-                            enums.push(typ_enum);
-
-                            if let Some(_) = parameter.optional {
-                                let v = quote! {
-                                    #[serde(skip_serializing_if="Option::is_none")]
-                                    #[serde(rename = #parameter_name)]
-                                    pub #p_name: Option<#enum_name>,
-                                };
-                                parameter_object.push(v);
-                            } else {
-                                let v = quote! {
-                                    #[serde(rename = #parameter_name)]
-                                    pub #p_name: #enum_name,
-                                };
-                                parameter_object.push(v);
-                            }
-                        } else {
-                            if let Some(_) = parameter.optional {
-                                let v = quote! {
-                                    #[serde(skip_serializing_if="Option::is_none")]
-                                    #[serde(default)]
-                                    #[serde(rename = #parameter_name)]
-                                    pub #p_name: Option<String>,
-                                };
-
-                                parameter_object.push(v);
-                            } else {
-                                let v = quote! {
-                                    #[serde(default)]
-                                    #[serde(rename = #parameter_name)]
-                                    pub #p_name: String,
-                                };
-
-                                parameter_object.push(v);
-                            }
-                        }
-                    }
-                    _ => {
-                        let type_type: Option<Ident> = param_type.into();
-
-                        if let Some(typ) = type_type {
-                            if let Some(_) = parameter.optional {
-                                let v = quote! {
-                                    #[serde(skip_serializing_if="Option::is_none")]
-                                    #[serde(default)]
-                                    #[serde(rename = #parameter_name)]
-                                    pub #p_name: Option<#typ>,
-                                };
-
-                                parameter_object.push(v);
-                            } else {
-                                let v = quote! {
-                                    #[serde(default)]
-                                    #[serde(rename = #parameter_name)]
-                                    pub #p_name: #typ,
-                                };
-
-                                parameter_object.push(v);
-                            }
-                        }
-                    }
-                }
-            } else {
-                let p_ref = &parameter.parameter_ref.clone().unwrap();
-
-                let parameter_name = &parameter.name;
-
-                let ret_type = Ident::new(
-                    &parameter_name.to_case(Case::Snake).replace("type", "Type"),
-                    Span::call_site(),
-                );
-
-                if p_ref.contains(".") {
-                    let dep = p_ref
-                        .split(".")
-                        .map(|v| Ident::new(v, Span::call_site()))
-                        .collect::<Vec<Ident>>();
-
-                    let v: Vec<&TokenStream> = dependencies
-                        .iter()
-                        .filter(|v| {
-                            let r = p_ref.split(".").collect::<Vec<&str>>()[0];
-                            v.to_string().contains(r)
-                        })
-                        .collect();
-
-                    if v.len() <= 0 {
-                        let first_dep = &dep[0];
-                        dependencies.push(quote! {
-                            use super::#first_dep;
-                        });
-                    }
+        let owner = Owner {
+            domain,
+            scope: Scope::Domain,
+            name: &command.name,
+            type_id: None,
+        };
+        let parameter_object = get_fields(parameters, &owner, context);
 
-                    if let Some(_) = parameter.optional {
-                        let v = quote! {
-                            #[serde(skip_serializing_if="Option::is_none")]
-                            #[serde(rename = #parameter_name)]
-                            pub #ret_type: Option<#(#dep)::*>,
-                        };
-                        parameter_object.push(v);
-                    } else {
-                        let v = quote! {
-                            #[serde(rename = #parameter_name)]
-                            pub #ret_type: #(#dep)::*,
-                        };
-                        parameter_object.push(v);
-                    }
-                } else {
-                    let p_ref = Ident::new(&p_ref, Span::call_site());
-
-                    if let Some(_) = parameter.optional {
-                        let v = quote! {
-                            #[serde(skip_serializing_if="Option::is_none")]
-                            #[serde(rename = #parameter_name)]
-                            pub #ret_type: Option<#p_ref>,
-                        };
-
-                        parameter_object.push(v);
-                    } else {
-                        let v = quote! {
-                            #[serde(rename = #parameter_name)]
-                            pub #ret_type: #p_ref,
-                        };
-
-                        parameter_object.push(v);
-                    }
-                }
-            }
-        }
+        let attributes = context
+            .attributes
+            .of_fields(domain, &command.name, parameters);
         parameter_objects.push(quote! {
-            #[derive(Deserialize,Serialize, Debug,Clone,PartialEq)]
-            // #[serde(rename_all = "camelCase")]
-            pub struct #name {
+            #doc
+            #attributes
+            pub struct #name #generics {
                 #(#parameter_object)*
             }
         });
     } else {
+        let attributes = context.attributes.of_json(domain, &command.name);
+        let json = context.lifetimes.json_field();
         parameter_objects.push(quote! {
-            #[derive(Deserialize,Serialize, Debug,Clone,PartialEq)]
+            #doc
+            #attributes
             #[serde(rename_all = "camelCase")]
-            pub struct #name(pub Option<serde_json::Value>);
+            pub struct #name #generics(#json);
         });
     }
 }
 
+/// The command a redirected command has moved to, see [`redirect_target`].
+fn target_command<'a>(command: &Command, protocols: &'a [Protocol]) -> &'a Command {
+    let target_domain = command.redirect.as_ref().unwrap();
+    protocols
+        .iter()
+        .find_map(|protocol| protocol.domain(target_domain)?.find_command(&command.name))
+        .unwrap()
+}
+
+/// Returns the `Domain.command` a redirected command has moved to, if the
+/// target domain still defines a command with the same name.
+fn redirect_target(command: &Command, symbols: &SymbolTable) -> Option<String> {
+    let target_method_name = format!("{}.{}", command.redirect.as_ref()?, command.name);
+
+    if symbols.has_command(&target_method_name) {
+        Some(target_method_name)
+    } else {
+        None
+    }
+}
+
+pub fn get_redirect(
+    domain: &str,
+    command: &Command,
+    target_method_name: &str,
+    context: &Context,
+    redirect_objects: &mut Vec<TokenStream>,
+) {
+    let target_domain = command.redirect.as_ref().unwrap();
+    let target_domain_ident = type_ident(target_domain);
+
+    let method_name = format!("{}.{}", domain, command.name);
+
+    let mut name = command.name.clone();
+    name.first_uppercase();
+    let name_ident = type_ident(&name);
+    let return_ident = type_ident(&format!("{}ReturnObject", name));
+
+    let doc = format!(
+        "`{}` has moved to `{}`. This wraps the `{}` definition but is still sent as `{}`.",
+        method_name, target_method_name, target_domain, method_name
+    );
+    let return_doc = format!("Return object of `{}`.", target_method_name);
+
+    let target = target_command(command, context.protocols);
+    let lifetimes = context.lifetimes;
+    let borrows = lifetimes.of_parameters(target_domain, target);
+    let generics = lifetimes.generics(borrows);
+    let borrow = if borrows {
+        quote! { #[serde(borrow)] }
+    } else {
+        quote! {}
+    };
+    let return_generics = lifetimes.generics(lifetimes.of_returns(target_domain, target));
+
+    let attributes = context
+        .attributes
+        .of_redirect(domain, &command.name, target_domain);
+    redirect_objects.push(quote! {
+        #[doc = #doc]
+        #attributes
+        #[serde(transparent)]
+        pub struct #name_ident #generics(#borrow pub super::#target_domain_ident::#name_ident #generics);
+
+        impl #generics From<super::#target_domain_ident::#name_ident #generics> for #name_ident #generics {
+            fn from(params: super::#target_domain_ident::#name_ident #generics) -> Self {
+                #name_ident(params)
+            }
+        }
+
+        #[doc = #return_doc]
+        pub type #return_ident #return_generics = super::#target_domain_ident::#return_ident #return_generics;
+    });
+}
+
 pub fn get_events(
-    event: Event,
+    domain: &str,
+    event: &Event,
+    context: &Context,
     event_objects: &mut Vec<TokenStream>,
-    enums: &mut Vec<TokenStream>,
 ) {
     let mut name = event.name.clone();
     name.first_uppercase();
     name.push_str("Event");
-    let name = Ident::new(&name, Span::call_site());
-    if let Some(parameters) = event.parameters {
-        let mut event_object = Vec::new();
-        for parameter in parameters {
-            let parameter_name = &parameter.name;
-            let p_name = Ident::new(
-                &parameter_name
-                    .to_case(Case::Snake)
-                    .replace("type", "Type")
-                    .replace("override", "Override"),
-                Span::call_site(),
-            );
-
-            if let Some(param_type) = parameter.parameter_type {
-                match param_type {
-                    TypeEnum::Array => {
-                        let items = parameter.items.as_ref().unwrap();
-
-                        if let Some(ref_type) = items.items_ref.clone() {
-                            if ref_type.contains(".") {
-                                let dep = ref_type
-                                    .split(".")
-                                    .map(|v| Ident::new(v, Span::call_site()))
-                                    .collect::<Vec<Ident>>();
-
-                                if let Some(_) = parameter.optional {
-                                    let v = quote! {
-                                        #[serde(skip_serializing_if="Option::is_none")]
-                                        #[serde(rename = #parameter_name)]
-                                        pub #p_name: Option<super::super::#(#dep)::*>,
-                                    };
-                                    event_object.push(v);
-                                } else {
-                                    let v = quote! {
-                                        #[serde(rename = #parameter_name)]
-                                        pub #p_name: super::super::#(#dep)::*,
-                                    };
-                                    event_object.push(v);
-                                }
-                            } else {
-                                let ref_type = Ident::new(&ref_type, Span::call_site());
-
-                                if let Some(_) = parameter.optional {
-                                    let v = quote! {
-                                        #[serde(skip_serializing_if="Option::is_none")]
-                                        #[serde(rename = #parameter_name)]
-                                        pub #p_name: Option<Vec<super::#ref_type>>,
-                                    };
-                                    event_object.push(v);
-                                } else {
-                                    let v = quote! {
-                                        #[serde(rename = #parameter_name)]
-                                        pub #p_name: Vec<super::#ref_type>,
-                                    };
-                                    event_object.push(v);
-                                }
-                            }
-                        } else {
-                            let type_type: Option<Ident> = items.items_type.unwrap().into();
-
-                            if let Some(typ) = type_type {
-                                if let Some(_) = parameter.optional {
-                                    let v = quote! {
-                                        #[serde(skip_serializing_if="Option::is_none")]
-                                        #[serde(default)]
-                                        #[serde(rename = #parameter_name)]
-                                        pub #p_name: Option<Vec<#typ>>,
-                                    };
-
-                                    event_object.push(v);
-                                } else {
-                                    let v = quote! {
-                                        #[serde(default)]
-                                        #[serde(rename = #parameter_name)]
-                                        pub #p_name: Vec<#typ>,
-                                    };
-
-                                    event_object.push(v);
-                                }
-                            }
-                        }
-                    }
-                    TypeEnum::String => {
-                        if let Some(enum_vec) = &parameter.parameter_enum {
-                            let (enum_name, typ_enum) = tokenize_enum(
-                                enum_vec,
-                                name.to_string()
-                                    + &p_name.to_string().first_uppercased()
-                                    + "Option",
-                            );
-                            enums.push(typ_enum);
-
-                            if let Some(_) = parameter.optional {
-                                let v = quote! {
-                                    #[serde(skip_serializing_if="Option::is_none")]
-                                    #[serde(rename = #parameter_name)]
-                                    pub #p_name: Option<super::#enum_name>,
-                                };
-                                event_object.push(v);
-                            } else {
-                                let v = quote! {
-                                    #[serde(rename = #parameter_name)]
-                                    pub #p_name: super::#enum_name,
-                                };
-                                event_object.push(v);
-                            }
-                        } else {
-                            if let Some(_) = parameter.optional {
-                                let v = quote! {
-                                    #[serde(skip_serializing_if="Option::is_none")]
-                                    #[serde(default)]
-                                    #[serde(rename = #parameter_name)]
-                                    pub #p_name: Option<String>,
-                                };
-
-                                event_object.push(v);
-                            } else {
-                                let v = quote! {
-                                    #[serde(default)]
-                                    #[serde(rename = #parameter_name)]
-                                    pub #p_name: String,
-                                };
-
-                                event_object.push(v);
-                            }
-                        }
-                    }
-                    _ => {
-                        let type_type: Option<Ident> = param_type.into();
-
-                        if let Some(typ) = type_type {
-                            if let Some(_) = parameter.optional {
-                                let v = quote! {
-                                    #[serde(skip_serializing_if="Option::is_none")]
-                                    #[serde(default)]
-                                    #[serde(rename = #parameter_name)]
-                                    pub #p_name: Option<#typ>,
-                                };
-
-                                event_object.push(v);
-                            } else {
-                                let v = quote! {
-                                    #[serde(default)]
-                                    #[serde(rename = #parameter_name)]
-                                    pub #p_name: #typ,
-                                };
-
-                                event_object.push(v);
-                            }
-                        }
-                    }
-                }
-            } else {
-                let p_ref = &parameter.parameter_ref.clone().unwrap();
-
-                let parameter_name = &parameter.name;
-
-                let ret_type = Ident::new(
-                    &parameter_name.to_case(Case::Snake).replace("type", "Type"),
-                    Span::call_site(),
-                );
+    let name = type_ident(&name);
+    let lifetimes = context.lifetimes;
+    let generics = lifetimes.generics(lifetimes.of_event(domain, event));
+    if let Some(parameters) = event.parameters.as_deref() {
+        let owner = Owner {
+            domain,
+            scope: Scope::Events,
+            name: &event.name,
+            type_id: None,
+        };
+        let event_object = get_fields(parameters, &owner, context);
+        let attributes = context
+            .attributes
+            .of_fields(domain, &event.name, parameters);
 
-                if p_ref.contains(".") {
-                    let dep = p_ref
-                        .split(".")
-                        .map(|v| Ident::new(v, Span::call_site()))
-                        .collect::<Vec<Ident>>();
-
-                    if let Some(_) = parameter.optional {
-                        let v = quote! {
-                            #[serde(skip_serializing_if="Option::is_none")]
-                            #[serde(rename = #parameter_name)]
-                            pub #ret_type: Option<super::super::#(#dep)::*>,
-                        };
-                        event_object.push(v);
-                    } else {
-                        let v = quote! {
-                            #[serde(rename = #parameter_name)]
-                            pub #ret_type: super::super::#(#dep)::*,
-                        };
-                        event_object.push(v);
-                    }
-                } else {
-                    let p_ref = Ident::new(&p_ref, Span::call_site());
-
-                    if let Some(_) = parameter.optional {
-                        let v = quote! {
-                            #[serde(skip_serializing_if="Option::is_none")]
-                            #[serde(rename = #parameter_name)]
-                            pub #ret_type: Option<super::#p_ref>,
-                        };
-
-                        event_object.push(v);
-                    } else {
-                        let v = quote! {
-                            #[serde(rename = #parameter_name)]
-                            pub #ret_type: super::#p_ref,
-                        };
-
-                        event_object.push(v);
-                    }
-                }
-            }
-        }
         let mut param_name = name.to_string();
         param_name.push_str("Params");
 
-        let param_ident = Ident::new(&param_name, Span::call_site());
+        let param_ident = type_ident(&param_name);
+        let borrow = if lifetimes.of_event(domain, event) {
+            quote! { #[serde(borrow)] }
+        } else {
+            quote! {}
+        };
+        let event_method = event_method(
+            domain,
+            event,
+            &name,
+            &generics,
+            context,
+            quote! {
+                serde_json::from_str(params.get()).map(|params| #name { params })
+            },
+        );
         event_objects.push(quote! {
-            #[derive(Deserialize,Serialize, Debug,Clone,PartialEq)]
-            pub struct #name {
-                pub params: #param_ident
+            #attributes
+            pub struct #name #generics {
+                #borrow
+                pub params: #param_ident #generics
             }
 
-            #[derive(Deserialize,Serialize, Debug, Clone, PartialEq)]
-            // #[serde(rename_all = "camelCase")]
-            pub struct #param_ident {
+            #attributes
+            pub struct #param_ident #generics {
                 #(#event_object)*
             }
+
+            #event_method
         });
     } else {
+        let attributes = context.attributes.of_json(domain, &event.name);
+        let json = lifetimes.json_field();
+        let event_method = event_method(
+            domain,
+            event,
+            &name,
+            &generics,
+            context,
+            quote! {
+                serde_json::from_str(params.get()).map(#name)
+            },
+        );
         event_objects.push(quote! {
-            #[derive(Deserialize,Serialize, Debug,Clone,PartialEq)]
+            #attributes
             #[serde(rename_all = "camelCase")]
-            pub struct #name(pub Option<serde_json::Value>);
+            pub struct #name #generics(#json);
+
+            #event_method
         });
     }
 }
 
-pub fn check_json(file_name: &str, commit: &str) -> Protocol {
+/// The `EventMethod` impl of the event struct `name`, building it from `params` with
+/// `from_params`, if raw events are generated.
+fn event_method(
+    domain: &str,
+    event: &Event,
+    name: &Ident,
+    generics: &TokenStream,
+    context: &Context,
+    from_params: TokenStream,
+) -> TokenStream {
+    let method = format!("{}.{}", domain, event.name);
+    if !context.options.has_raw_events() {
+        quote! {}
+    } else if context.lifetimes.borrowed() {
+        quote! {
+            impl<'a> EventMethod<'a> for #name #generics {
+                const NAME: &'static str = #method;
+
+                fn from_params(params: &'a RawValue) -> serde_json::Result<Self> {
+                    #from_params
+                }
+            }
+        }
+    } else {
+        quote! {
+            impl EventMethod for #name {
+                const NAME: &'static str = #method;
+
+                fn from_params(params: &serde_json::value::RawValue) -> serde_json::Result<Self> {
+                    #from_params
+                }
+            }
+        }
+    }
+}
+
+/// Path of the protocol file `file_name` in the `json` directory of the crate at
+/// `manifest_dir`.
+fn local_json_path(manifest_dir: &str, file_name: &str) -> PathBuf {
+    Path::new(manifest_dir).join("json").join(file_name)
+}
+
+fn read_local_json(manifest_dir: &str, file_name: &str) -> String {
+    std::fs::read_to_string(local_json_path(manifest_dir, file_name)).unwrap()
+}
+
+/// Tells cargo to rerun the build script when [`fetch_json`] would read something
+/// else for `file_name`.
+pub fn print_rerun_directives(file_name: &str) {
     #[cfg(feature = "offline")]
     if cfg!(feature = "offline") {
-        let path = Path::new(MANIFEST_DIR).join("json").join(file_name);
+        let path = local_json_path(MANIFEST_DIR, file_name);
+        println!("cargo:rerun-if-changed={}", path.display());
+        return;
+    }
 
-        let json = std::fs::read_to_string(path).unwrap();
+    println!("cargo:rerun-if-env-changed=DOCS_RS");
 
-        let protocol: Protocol = serde_json::from_str(&json).unwrap();
+    if let Ok(manifest_dir) = env::var("CARGO_MANIFEST_DIR") {
+        if env::var("DOCS_RS").is_ok() {
+            let path = local_json_path(&manifest_dir, file_name);
+            println!("cargo:rerun-if-changed={}", path.display());
+            return;
+        }
+    }
+
+    for var in ["https_proxy", "http_proxy", "ALL_PROXY"].iter() {
+        println!("cargo:rerun-if-env-changed={}", var);
+    }
+}
 
-        return protocol;
+/// Returns the contents of the protocol file `file_name` at `commit`.
+pub fn fetch_json(file_name: &str, commit: &str) -> String {
+    #[cfg(feature = "offline")]
+    if cfg!(feature = "offline") {
+        return read_local_json(MANIFEST_DIR, file_name);
     }
 
     if std::env::var("DOCS_RS").is_ok() {
         // code to run when building inside a docs.rs environment
 
         let path = std::env::var("CARGO_MANIFEST_DIR").unwrap();
-        let path = Path::new(&path).join("json").join(file_name);
-        let json = std::fs::read_to_string(path).unwrap();
-
-        let protocol: Protocol = serde_json::from_str(&json).unwrap();
-
-        protocol
+        read_local_json(&path, file_name)
     } else {
         let ureq_agent = {
             let mut builder = ureq::AgentBuilder::new();
 
             // use HTTP proxy from environment variables if available
             if let Ok(addr) = env::var("https_proxy")
-                .or(env::var("http_proxy"))
-                .or(env::var("ALL_PROXY"))
+                .or_else(|_| env::var("http_proxy"))
+                .or_else(|_| env::var("ALL_PROXY"))
             {
                 let proxy = ureq::Proxy::new(addr)
                     .expect("Invalid proxy specified in environment variables");
@@ -1309,7 +701,7 @@ pub fn check_json(file_name: &str, commit: &str) -> Protocol {
             commit, file_name
         );
 
-        let json = ureq_agent
+        ureq_agent
             .get(&url)
             .call()
             .expect(
@@ -1318,25 +710,66 @@ pub fn check_json(file_name: &str, commit: &str) -> Protocol {
                 in that order.",
             )
             .into_string()
-            .expect("Received JSON is not valid UTF8");
+            .expect("Received JSON is not valid UTF8")
+    }
+}
 
-        let protocol: Protocol = serde_json::from_str(&json).unwrap();
+/// Fetches the protocol a browser serves at `url`, e.g.
+/// `http://localhost:9222/json/protocol`.
+pub fn fetch_endpoint(url: &str) -> String {
+    ureq::get(url)
+        .call()
+        .unwrap_or_else(|err| panic!("could not fetch the protocol from {}: {}", url, err))
+        .into_string()
+        .expect("Received JSON is not valid UTF8")
+}
 
-        protocol
-    }
+/// Generated code of one domain.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct DomainModule {
+    /// Protocol name of the domain, e.g. `DOM`.
+    pub domain: String,
+    /// Rust name of the module.
+    pub name: Ident,
+    /// Other domains the module refers to, including the targets of redirects.
+    pub dependencies: BTreeSet<String>,
+    /// Contents of the module.
+    pub body: TokenStream,
+    /// Variants of the `Event` enum for the events of the domain, as
+    /// [`types_module`](crate::codegen::types_module) takes them.
+    pub events: Vec<TokenStream>,
+    /// Entries of `METHODS` for the commands of the domain, as
+    /// [`methods`](crate::codegen::methods) takes them.
+    pub methods: Vec<TokenStream>,
+    /// Problems that didn't stop the generation of the module, e.g. derives
+    /// requested for a type that cannot have them.
+    pub warnings: Vec<String>,
 }
 
-pub fn compile_cdp_json(file_name: &str, commit: &str) -> (Vec<TokenStream>, Vec<TokenStream>) {
-    let protocol = check_json(file_name, commit);
+/// Generates the module of every domain in `protocols`.
+pub fn compile_cdp_json(protocols: &[Protocol], options: &Options) -> Vec<DomainModule> {
+    for protocol in protocols {
+        check_collisions(protocol);
+    }
+
+    let symbols = SymbolTable::new(protocols).with_overrides(&options.overrides);
+    let attributes = Attributes::new(protocols, &symbols, options);
+    let lifetimes = Lifetimes::new(protocols, &symbols, options);
+    let context = Context {
+        protocols,
+        symbols: &symbols,
+        attributes: &attributes,
+        lifetimes: &lifetimes,
+        options,
+    };
 
     let mut mods = Vec::new();
-    let mut event_parts = Vec::new();
 
-    for dom in protocol.domains {
+    for dom in protocols.iter().flat_map(|protocol| &protocol.domains) {
         let mut types = Vec::new();
         let mut enums = Vec::new();
         let mut objects = Vec::new();
-        let mut dependencies = Vec::new();
 
         let mut command_objects = Vec::new();
 
@@ -1346,77 +779,164 @@ pub fn compile_cdp_json(file_name: &str, commit: &str) -> (Vec<TokenStream>, Vec
 
         let mut method_impls = Vec::new();
 
-        if let Some(deps) = &dom.dependencies {
-            for dep in deps
-                .iter()
-                .map(|v| Ident::new(&v.trim(), Span::call_site()))
-                .collect::<Vec<Ident>>()
-            {
-                dependencies.push(quote! {
-                    use super::#dep;
-                });
+        let mut redirect_objects = Vec::new();
+
+        let mut event_parts = Vec::new();
+
+        let mut method_infos = Vec::new();
+
+        let mut dependencies: BTreeSet<String> = symbols
+            .dependencies(&dom.domain)
+            .map(|dep| dep.to_string())
+            .collect();
+
+        let imports = symbols.dependencies(&dom.domain).map(|dep| {
+            let dep = type_ident(dep);
+            quote! {
+                use super::#dep;
             }
-        }
+        });
 
         if let Some(type_elements) = dom.types.as_deref() {
             for type_element in type_elements {
                 get_types(
-                    type_element.type_type,
-                    PropertyType::Element(type_element),
-                    Some(type_element),
+                    &dom.domain,
+                    type_element,
+                    &context,
                     &mut types,
                     &mut enums,
                     &mut objects,
-                    &mut Vec::new(),
-                    &mut dependencies,
-                    None,
                 );
             }
         }
 
+        for inline_enum in symbols.inline_enums(&dom.domain) {
+            let (_, typ_enum) = tokenize_enum(
+                inline_enum.values,
+                inline_enum.name.clone(),
+                attributes.of_enum(&dom.domain, inline_enum.owner),
+                options.lenient(),
+            );
+            enums.push(typ_enum);
+
+            let name = type_ident(&inline_enum.name);
+            for alias in &inline_enum.aliases {
+                let alias = type_ident(alias);
+                enums.push(quote! {
+                    pub type #alias = #name;
+                });
+            }
+        }
+
         get_commands(
+            &dom.domain,
             &dom.commands,
-            &mut dependencies,
+            &context,
             &mut command_objects,
             &mut parameter_objects,
-            &mut enums,
         );
 
         for command in &dom.commands {
+            if let Some(target_method_name) = redirect_target(command, &symbols) {
+                dependencies.extend(command.redirect.clone());
+                get_redirect(
+                    &dom.domain,
+                    command,
+                    &target_method_name,
+                    &context,
+                    &mut redirect_objects,
+                );
+            }
+
             let mut cmd_name = command.name.clone();
-            let mut method_name = String::from(dom.domain.clone());
+            let mut method_name = dom.domain.clone();
             method_name.push_str(&format!(".{}", cmd_name));
             cmd_name.first_uppercase();
 
-            let method_ident = Ident::new(&cmd_name, Span::call_site());
+            let method_ident = type_ident(&cmd_name);
 
             let mut method_return_obj = cmd_name.clone();
 
             method_return_obj.push_str("ReturnObject");
 
-            let method_return_obj = Ident::new(&method_return_obj, Span::call_site());
+            let method_return_obj = type_ident(&method_return_obj);
+
+            let domain = &dom.domain;
+            let experimental =
+                command.experimental.unwrap_or(false) || dom.experimental.unwrap_or(false);
+            let deprecated = command.deprecated.unwrap_or(false);
+            let flags = quote! {
+                const DOMAIN: &'static str = #domain;
+                const IS_EXPERIMENTAL: bool = #experimental;
+                const IS_DEPRECATED: bool = #deprecated;
+            };
+            // a redirect serializes the target command, so it takes the target's parameters
+            let serialized = match redirect_target(command, &symbols) {
+                Some(_) => target_command(command, protocols),
+                None => command,
+            };
+            let parameters = serialized
+                .parameters
+                .iter()
+                .flatten()
+                .map(|param| &param.name);
+            method_infos.push(quote! {
+                types::MethodInfo {
+                    name: #method_name,
+                    domain: #domain,
+                    experimental: #experimental,
+                    deprecated: #deprecated,
+                    parameters: &[#(#parameters),*],
+                },
+            });
 
-            let v = quote! {
-                impl Method for #method_ident {
-                    const NAME: &'static str = #method_name;
-                    type ReturnObject = #method_return_obj;
+            let v = if lifetimes.borrowed() {
+                // redirect wrappers borrow like the command they wrap
+                let (domain, target) = match redirect_target(command, &symbols) {
+                    Some(_) => (
+                        command.redirect.as_deref().unwrap(),
+                        target_command(command, protocols),
+                    ),
+                    None => (dom.domain.as_str(), command),
+                };
+                let generics = lifetimes.generics(lifetimes.of_parameters(domain, target));
+                let return_generics = if lifetimes.of_returns(domain, target) {
+                    quote! { <'de> }
+                } else {
+                    quote! {}
+                };
+
+                quote! {
+                    impl #generics Method for #method_ident #generics {
+                        const NAME: &'static str = #method_name;
+                        #flags
+                        type ReturnObject<'de> = #method_return_obj #return_generics;
+                    }
+                }
+            } else {
+                quote! {
+                    impl Method for #method_ident {
+                        const NAME: &'static str = #method_name;
+                        #flags
+                        type ReturnObject = #method_return_obj;
+                    }
                 }
             };
 
             method_impls.push(v);
         }
 
-        if let Some(events) = dom.events {
+        if let Some(events) = &dom.events {
             for event in events {
                 let event_name = event.name.clone();
 
-                get_events(event, &mut event_objects, &mut enums);
+                get_events(&dom.domain, event, &context, &mut event_objects);
 
                 let mut domain_event = dom.domain.clone();
 
                 domain_event.push_str(&format!(".{}", event_name));
 
-                let domain_ident = Ident::new(&dom.domain.clone(), Span::call_site());
+                let domain_ident = type_ident(&dom.domain);
 
                 let mut name = event_name.clone();
 
@@ -1430,31 +950,50 @@ pub fn compile_cdp_json(file_name: &str, commit: &str) -> (Vec<TokenStream>, Vec
 
                 enum_name.push_str(&name);
 
-                let enum_name = Ident::new(&enum_name, Span::call_site());
+                let enum_name = type_ident(&enum_name);
 
                 name.push_str("Event");
 
-                let name = Ident::new(&name, Span::call_site());
+                let name = type_ident(&name);
 
-                let v = quote! {
-                    #[serde(rename = #domain_event)]
-                    #enum_name(super::#domain_ident::events::#name),
+                let v = if lifetimes.borrowed() {
+                    let generics = lifetimes.generics(lifetimes.of_event(&dom.domain, event));
+                    quote! {
+                        #domain_event => #enum_name(super::#domain_ident::events::#name #generics),
+                    }
+                } else {
+                    quote! {
+                        #[serde(rename = #domain_event)]
+                        #enum_name(super::#domain_ident::events::#name),
+                    }
                 };
 
                 event_parts.push(v);
             }
         }
 
-        let domain_ident = Ident::new(&dom.domain, Span::call_site());
+        let helpers = runtime::helpers(dom, &symbols, &lifetimes);
 
-        mods.push(quote! {
-            pub mod #domain_ident {
+        let borrowed_imports = if lifetimes.borrowed() {
+            quote! {
+                use serde_json::value::RawValue;
+                use std::borrow::Cow;
+            }
+        } else {
+            quote! {}
+        };
 
+        mods.push(DomainModule {
+            domain: dom.domain.clone(),
+            name: type_ident(&dom.domain),
+            dependencies,
+            body: quote! {
                 use serde::{Deserialize, Serialize};
                 use serde_json::Value as Json;
+                #borrowed_imports
                 use super::types::*;
 
-                #(#dependencies)*
+                #(#imports)*
 
                 #(#types)*
 
@@ -1466,19 +1005,25 @@ pub fn compile_cdp_json(file_name: &str, commit: &str) -> (Vec<TokenStream>, Vec
 
                 #(#command_objects)*
 
+                #(#redirect_objects)*
 
                 #(#method_impls)*
 
+                #helpers
+
                 pub mod events {
                     use serde::{Deserialize, Serialize};
+                    #borrowed_imports
                     use super::super::types::*;
 
                     #(#event_objects)*
                 }
-
-            }
+            },
+            events: event_parts,
+            methods: method_infos,
+            warnings: attributes.dropped(&dom.domain),
         });
     }
 
-    (mods, event_parts)
+    mods
 }
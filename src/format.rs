@@ -0,0 +1,199 @@
+use std::env;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::thread;
+
+use proc_macro2::{Delimiter, Spacing, TokenStream, TokenTree};
+
+/// How the generated code is laid out.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    /// One item, field or statement per line without running a formatter. This is
+    /// what `DO_NOT_FORMAT` selects.
+    Plain,
+    /// `rustfmt`, or the binary in `$RUSTFMT`.
+    Rustfmt,
+    /// `prettyplease`, in process and without needing a toolchain component.
+    #[cfg(feature = "pretty")]
+    Pretty,
+}
+
+impl Default for Format {
+    fn default() -> Self {
+        if env::var_os("DO_NOT_FORMAT").is_some() {
+            return Format::Plain;
+        }
+
+        #[cfg(feature = "pretty")]
+        return Format::Pretty;
+
+        #[cfg(not(feature = "pretty"))]
+        Format::Rustfmt
+    }
+}
+
+/// Formats `tokens`, falling back to [`Format::Plain`] and passing the error to `warn`
+/// when the chosen formatter fails.
+pub fn format(tokens: &TokenStream, format: Format, warn: impl Fn(&str)) -> String {
+    let formatted = match format {
+        Format::Plain => return plain(tokens.clone()),
+        Format::Rustfmt => rustfmt(&tokens.to_string()),
+        #[cfg(feature = "pretty")]
+        Format::Pretty => syn::parse2(tokens.clone())
+            .map(|file| prettyplease::unparse(&file))
+            .map_err(|err| format!("prettyplease could not parse the generated code: {}", err)),
+    };
+
+    formatted.unwrap_or_else(|err| {
+        warn(&err);
+        plain(tokens.clone())
+    })
+}
+
+fn rustfmt(code: &str) -> Result<String, String> {
+    let rustfmt = env::var_os("RUSTFMT").unwrap_or_else(|| "rustfmt".into());
+
+    let mut child = Command::new(&rustfmt)
+        .args(["--edition", "2018", "--emit", "stdout"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| format!("could not run {}: {}", rustfmt.to_string_lossy(), err))?;
+
+    // write from another thread so that a full stdout pipe can't block rustfmt
+    let mut stdin = child.stdin.take().unwrap();
+    let code = code.to_string();
+    let writer = thread::spawn(move || stdin.write_all(code.as_bytes()));
+
+    let output = child.wait_with_output().map_err(|err| err.to_string())?;
+    writer
+        .join()
+        .unwrap()
+        .map_err(|err| format!("could not write to rustfmt: {}", err))?;
+
+    if output.status.success() {
+        String::from_utf8(output.stdout).map_err(|err| err.to_string())
+    } else {
+        Err(format!(
+            "rustfmt failed ({}):\n{}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr)
+        ))
+    }
+}
+
+/// Prints `tokens` with line breaks after items, fields and attributes, which is
+/// enough to make compiler errors point at a readable line.
+fn plain(tokens: TokenStream) -> String {
+    let mut out = String::new();
+    write_plain(&mut out, tokens, 0);
+    newline(&mut out, 0);
+    out
+}
+
+fn newline(out: &mut String, indent: usize) {
+    let trimmed = out.trim_end_matches(' ').len();
+    out.truncate(trimmed);
+    if !out.is_empty() && !out.ends_with('\n') {
+        out.push('\n');
+    }
+    for _ in 0..indent {
+        out.push_str("    ");
+    }
+}
+
+fn write_plain(out: &mut String, tokens: TokenStream, indent: usize) {
+    let mut attribute = false;
+    let mut path = false;
+
+    for token in tokens {
+        let next_attribute = match &token {
+            TokenTree::Punct(punct) => {
+                punct.as_char() == '#' || (attribute && punct.as_char() == '!')
+            }
+            _ => false,
+        };
+
+        match token {
+            // `use` lists stay on one line
+            TokenTree::Group(group) if group.delimiter() == Delimiter::Brace && path => {
+                out.push_str(&format!("{{ {} }} ", group.stream()));
+            }
+            TokenTree::Group(group) if group.delimiter() == Delimiter::Brace => {
+                out.push('{');
+                if !group.stream().is_empty() {
+                    newline(out, indent + 1);
+                    write_plain(out, group.stream(), indent + 1);
+                    newline(out, indent);
+                }
+                out.push('}');
+                newline(out, indent);
+            }
+            TokenTree::Group(group) => {
+                let (open, close) = match group.delimiter() {
+                    Delimiter::Parenthesis => ("(", ")"),
+                    Delimiter::Bracket => ("[", "]"),
+                    _ => ("", ""),
+                };
+                out.push_str(open);
+                out.push_str(&group.stream().to_string());
+                out.push_str(close);
+
+                if attribute {
+                    newline(out, indent);
+                } else {
+                    out.push(' ');
+                }
+            }
+            TokenTree::Punct(punct) => match punct.as_char() {
+                ';' | ',' => {
+                    let trimmed = out.trim_end_matches(' ').len();
+                    out.truncate(trimmed);
+                    out.push(punct.as_char());
+                    newline(out, indent);
+                }
+                '#' | '!' if next_attribute => out.push(punct.as_char()),
+                c => {
+                    out.push(c);
+                    if punct.spacing() == Spacing::Alone {
+                        out.push(' ');
+                    }
+                }
+            },
+            token => {
+                out.push_str(&token.to_string());
+                out.push(' ');
+            }
+        }
+
+        attribute = next_attribute;
+        path = out.trim_end().ends_with("::");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use quote::quote;
+
+    #[test]
+    fn plain_breaks_lines() {
+        let tokens = quote! {
+            pub mod cdp {
+                #[derive(Debug)]
+                pub struct Foo {
+                    pub bar: Vec<String>,
+                }
+                pub type Baz<'a> = &'a str;
+                use serde::{Deserialize, Serialize};
+            }
+        };
+
+        assert_eq!(
+            plain(tokens),
+            "pub mod cdp {\n    #[derive (Debug)]\n    pub struct Foo {\n        pub bar : Vec < String >,\n    }\n    pub type Baz < 'a > = & 'a str;\n    use serde :: { Deserialize , Serialize };\n}\n"
+        );
+    }
+}
@@ -0,0 +1,187 @@
+//! Objects without properties, which hold any keys, as [`Options::free_form`]
+//! generates them.
+
+use proc_macro2::{Ident, TokenStream};
+use quote::quote;
+
+use crate::attributes::matching;
+use crate::codegen::{FreeForm, Options};
+use crate::lifetimes::Lifetimes;
+use crate::types::TypeEnum;
+
+/// A free-form object type, or field declared as an inline object.
+#[derive(Clone, Copy)]
+pub struct FreeFormObject {
+    form: FreeForm,
+    /// Whether it holds HTTP headers, whose values are strings and names are
+    /// compared ignoring case.
+    headers: bool,
+}
+
+impl FreeFormObject {
+    /// The protocol type `id` of `domain`.
+    pub fn of_type(options: &Options, domain: &str, id: &str) -> Self {
+        Self::new(options, domain, id, id.ends_with("Headers"))
+    }
+
+    /// An inline object field of the type, command or event `name`.
+    pub fn inline(options: &Options, domain: &str, name: &str) -> Self {
+        Self::new(options, domain, name, false)
+    }
+
+    fn new(options: &Options, domain: &str, name: &str, headers: bool) -> Self {
+        let default = if headers {
+            FreeForm::HashMap
+        } else {
+            FreeForm::BTreeMap
+        };
+        let form = matching(&options.free_forms, domain, name)
+            .last()
+            .copied()
+            .unwrap_or(default);
+
+        FreeFormObject { form, headers }
+    }
+
+    /// The JSON type of the values of the map, `None` if it isn't one.
+    pub fn values(&self) -> Option<TypeEnum> {
+        match self.form {
+            FreeForm::Value => None,
+            FreeForm::HashMap | FreeForm::BTreeMap if self.headers => Some(TypeEnum::String),
+            FreeForm::HashMap | FreeForm::BTreeMap => Some(TypeEnum::Any),
+        }
+    }
+
+    /// Whether the map is a `HashMap`, which has neither `Hash` nor an order.
+    pub fn hashed(&self) -> bool {
+        matches!(self.form, FreeForm::HashMap)
+    }
+
+    /// Whether it takes a lifetime in borrowed mode. Header names and values are
+    /// owned, as serde doesn't borrow the strings of maps.
+    pub fn borrows(&self, lifetimes: &Lifetimes) -> bool {
+        lifetimes.borrowed() && !matches!(self.values(), Some(TypeEnum::String))
+    }
+
+    /// The serde attributes reading `null` as an empty map, which the protocol sends
+    /// for some of these objects. `Option`s read it as `None` instead.
+    pub fn field_attributes(&self) -> TokenStream {
+        if self.values().is_some() {
+            quote! { #[serde(deserialize_with = "null_as_empty")] }
+        } else {
+            quote! {}
+        }
+    }
+
+    /// Rust type of the object.
+    pub fn rust_type(&self, lifetimes: &Lifetimes) -> TokenStream {
+        let values = match self.values() {
+            Some(TypeEnum::String) => quote! { String },
+            Some(_) if lifetimes.borrowed() => quote! { &'a RawValue },
+            Some(_) => quote! { serde_json::Value },
+            None if lifetimes.borrowed() => return quote! { Option<&'a RawValue> },
+            None => return quote! { Option<serde_json::Value> },
+        };
+
+        if self.hashed() {
+            quote! { std::collections::HashMap<String, #values> }
+        } else {
+            quote! { std::collections::BTreeMap<String, #values> }
+        }
+    }
+
+    /// The lookups ignoring case of the header type `name`, nothing for other types.
+    pub fn helpers(&self, name: &Ident) -> TokenStream {
+        if !matches!(self.values(), Some(TypeEnum::String)) {
+            return quote! {};
+        }
+
+        quote! {
+            impl #name {
+                /// The value of the header `name`, ignoring case.
+                pub fn get(&self, name: &str) -> Option<&str> {
+                    self.0
+                        .iter()
+                        .find(|(header, _)| header.eq_ignore_ascii_case(name))
+                        .map(|(_, value)| value.as_str())
+                }
+
+                /// Whether there is a header `name`, ignoring case.
+                pub fn contains(&self, name: &str) -> bool {
+                    self.get(name).is_some()
+                }
+
+                /// Sets the header `name` to `value`, replacing the one with the same name
+                /// in any case and returning its value.
+                pub fn insert(&mut self, name: impl Into<String>, value: impl Into<String>) -> Option<String> {
+                    let name = name.into();
+                    let previous = self.remove(&name);
+                    self.0.insert(name, value.into());
+                    previous
+                }
+
+                /// Removes the header `name`, ignoring case, returning its value.
+                pub fn remove(&mut self, name: &str) -> Option<String> {
+                    let header = self
+                        .0
+                        .keys()
+                        .find(|header| header.eq_ignore_ascii_case(name))?
+                        .clone();
+                    self.0.remove(&header)
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::symbols::SymbolTable;
+    use crate::types::Protocol;
+
+    #[test]
+    fn picks_forms() {
+        let protocols = [Protocol::from_json(
+            r#"{ "version": { "major": "1", "minor": "3" }, "domains": [] }"#,
+        )
+        .unwrap()];
+        let symbols = SymbolTable::new(&protocols);
+        let options = Options::new()
+            .free_form("Tracing", FreeForm::Value)
+            .free_form("Tracing.MemoryDumpConfig", FreeForm::HashMap);
+        let lifetimes = Lifetimes::new(&protocols, &symbols, &options);
+        let rust_type = |object: FreeFormObject| object.rust_type(&lifetimes).to_string();
+
+        assert_eq!(
+            rust_type(FreeFormObject::of_type(&options, "Network", "Headers")),
+            "std :: collections :: HashMap < String , String >"
+        );
+        assert_eq!(
+            rust_type(FreeFormObject::inline(
+                &options,
+                "Runtime",
+                "ExecutionContextDescription"
+            )),
+            "std :: collections :: BTreeMap < String , serde_json :: Value >"
+        );
+        assert_eq!(
+            rust_type(FreeFormObject::inline(&options, "Tracing", "dataCollected")),
+            "Option < serde_json :: Value >"
+        );
+        assert_eq!(
+            rust_type(FreeFormObject::of_type(
+                &options,
+                "Tracing",
+                "MemoryDumpConfig"
+            )),
+            "std :: collections :: HashMap < String , serde_json :: Value >"
+        );
+
+        let headers = Ident::new("Headers", proc_macro2::Span::call_site());
+        let helpers = FreeFormObject::of_type(&options, "Network", "Headers").helpers(&headers);
+        assert!(helpers.to_string().contains("eq_ignore_ascii_case"));
+        let config = FreeFormObject::of_type(&options, "Tracing", "MemoryDumpConfig");
+        assert!(config.helpers(&headers).is_empty());
+    }
+}
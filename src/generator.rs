@@ -0,0 +1,1006 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use serde_json::Value;
+
+use crate::codegen::{self, DomainModule, FreeForm, MissingFields, Options, TimeCrate};
+use crate::compile::{fetch_endpoint, fetch_json, print_rerun_directives};
+use crate::format::{format, Format};
+use crate::overlay;
+use crate::pdl;
+use crate::types::Protocol;
+
+const CDP_COMMIT: &str = "4f13107aac59fe418043f9edfdaef3b7da579614";
+
+/// The protocol of Node's inspector (`src/inspector/node_protocol.pdl`) on top of
+/// `js_protocol.json`, which isn't published at devtools-protocol commits.
+const NODE_PROTOCOL: &str = include_str!("../json/node_protocol.json");
+
+/// Starts the line of the root module of [`Layout::PerDomain`] listing the other
+/// files, after the header.
+const FILES: &str = "// Files: ";
+
+/// How the generated modules are split into files.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Layout {
+    /// Everything in the output file.
+    SingleFile,
+    /// `cdp/mod.rs`, `cdp/types.rs` and `cdp/<Domain>.rs` next to the output file,
+    /// which then only declares the `cdp` module. Compiler errors and IDE
+    /// navigation land in the file of the domain instead of one huge file.
+    PerDomain,
+}
+
+/// Which runtime's protocol [`Source::Commit`] generates.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Preset {
+    /// `js_protocol.json` and `browser_protocol.json`, what Chrome speaks.
+    Chrome,
+    /// `js_protocol.json` and the `NodeTracing`, `NodeWorker`, `NodeRuntime` and
+    /// `Network` domains of `node --inspect`, vendored in this crate.
+    Node,
+}
+
+impl Preset {
+    /// The files fetched at the commit.
+    fn files(self) -> &'static [&'static str] {
+        match self {
+            Preset::Chrome => &["js_protocol.json", "browser_protocol.json"],
+            Preset::Node => &["js_protocol.json"],
+        }
+    }
+}
+
+/// Where the protocol is read from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Source {
+    /// The protocol files of the [`Preset`] at a ChromeDevTools/devtools-protocol
+    /// commit, read from this crate with the `offline` feature.
+    Commit(String),
+    /// Local protocol files, either JSON or, with a `.pdl` extension, the protocol
+    /// definition language.
+    Files(Vec<PathBuf>),
+    /// The protocol a running browser implements, e.g.
+    /// `http://localhost:9222/json/protocol`. It is requested directly, without the
+    /// proxy used for GitHub.
+    Endpoint(String),
+}
+
+/// Generates the protocol bindings, `init()` runs it with the default options.
+///
+/// ```no_run
+/// // build.rs
+/// auto_generate_cdp::Generator::new()
+///     .commit("4f13107aac59fe418043f9edfdaef3b7da579614")
+///     .generate();
+/// ```
+///
+/// The output starts with a fingerprint of everything it was generated from, so it
+/// is only rewritten when the protocol files, the options or this crate change.
+#[derive(Debug)]
+pub struct Generator {
+    source: Source,
+    preset: Preset,
+    extra_files: Vec<PathBuf>,
+    overlays: Vec<PathBuf>,
+    domains: Vec<String>,
+    options: Options,
+    out_file: Option<PathBuf>,
+    format: Format,
+    layout: Layout,
+}
+
+impl Default for Generator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Generator {
+    pub fn new() -> Self {
+        Generator {
+            source: Source::Commit(CDP_COMMIT.to_string()),
+            preset: Preset::Chrome,
+            extra_files: Vec::new(),
+            overlays: Vec::new(),
+            domains: Vec::new(),
+            options: Options::new(),
+            out_file: None,
+            format: Format::default(),
+            layout: Layout::SingleFile,
+        }
+    }
+
+    /// Where to read the protocol from, the commit this version of the crate was
+    /// tested with by default.
+    pub fn source(mut self, source: Source) -> Self {
+        self.source = source;
+        self
+    }
+
+    /// Which runtime's protocol to generate from a commit, [`Preset::Chrome`] by
+    /// default.
+    pub fn preset(mut self, preset: Preset) -> Self {
+        self.preset = preset;
+        self
+    }
+
+    /// Shorthand for [`Source::Commit`].
+    pub fn commit(self, commit: &str) -> Self {
+        self.source(Source::Commit(commit.to_string()))
+    }
+
+    /// Shorthand for [`Source::Files`].
+    pub fn files<I, P>(self, files: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<PathBuf>,
+    {
+        self.source(Source::Files(files.into_iter().map(Into::into).collect()))
+    }
+
+    /// Protocol files generated along with the source, e.g. for the domains of a
+    /// Chromium fork. A domain can only be defined once.
+    pub fn extra_files<I, P>(mut self, files: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<PathBuf>,
+    {
+        self.extra_files = files.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// JSON-patch-style overlays applied in order before generating, which add,
+    /// replace or remove domains, types, commands, events, fields and enum values:
+    ///
+    /// ```json
+    /// [
+    ///     { "op": "add", "path": "/domains/Page/commands/navigate/parameters/acmeMode",
+    ///       "value": { "name": "acmeMode", "type": "boolean", "optional": true } },
+    ///     { "op": "remove", "path": "/domains/Page/types/TransitionType/enum/typed" }
+    /// ]
+    /// ```
+    ///
+    /// Paths name list elements: domains by `domain`, types by `id`, commands,
+    /// events and fields by `name` and enum values by themselves. Adding something
+    /// that exists or replacing or removing something that doesn't is an error.
+    pub fn overlays<I, P>(mut self, overlays: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<PathBuf>,
+    {
+        self.overlays = overlays.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Only generate these domains and the ones they depend on, all of them by
+    /// default.
+    pub fn domains<I, S>(mut self, domains: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.domains = domains.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Derives `derive` for the types at `path`, see [`Options::derive`]:
+    ///
+    /// ```no_run
+    /// auto_generate_cdp::Generator::new()
+    ///     .derive("*", "Eq")
+    ///     .derive("*", "Hash")
+    ///     .derive("Page", "schemars::JsonSchema")
+    ///     .derive("Page.FrameTree", "Default")
+    ///     .generate();
+    /// ```
+    pub fn derive(mut self, path: impl Into<String>, derive: impl Into<String>) -> Self {
+        self.options = self.options.derive(path, derive);
+        self
+    }
+
+    /// Adds `attribute` to the types at `path`, see [`Options::attribute`].
+    pub fn attribute(mut self, path: impl Into<String>, attribute: impl Into<String>) -> Self {
+        self.options = self.options.attribute(path, attribute);
+        self
+    }
+
+    /// Generates types that borrow from the input, see [`Options::borrowed`].
+    pub fn borrowed(mut self, borrowed: bool) -> Self {
+        self.options = self.options.borrowed(borrowed);
+        self
+    }
+
+    /// Generates `RawEvent` for decoding events lazily, see [`Options::raw_events`].
+    pub fn raw_events(mut self, raw_events: bool) -> Self {
+        self.options = self.options.raw_events(raw_events);
+        self
+    }
+
+    /// Generates wrappers of the types holding times, see [`Options::time_types`].
+    pub fn time_types(mut self, time_types: bool) -> Self {
+        self.options = self.options.time_types(time_types);
+        self
+    }
+
+    /// Converts the time types to the types of `time_crate` too, see
+    /// [`Options::time_crate`].
+    pub fn time_crate(mut self, time_crate: TimeCrate) -> Self {
+        self.options = self.options.time_crate(time_crate);
+        self
+    }
+
+    /// Makes missing required fields an error or gives them their default value, see
+    /// [`Options::missing_fields`].
+    pub fn missing_fields(mut self, missing_fields: MissingFields) -> Self {
+        self.options = self.options.missing_fields(missing_fields);
+        self
+    }
+
+    /// Generates the objects without properties at `path` as `form`, see
+    /// [`Options::free_form`]:
+    ///
+    /// ```no_run
+    /// use auto_generate_cdp::FreeForm;
+    ///
+    /// auto_generate_cdp::Generator::new()
+    ///     .free_form("Network.Headers", FreeForm::BTreeMap)
+    ///     .free_form("Tracing", FreeForm::Value)
+    ///     .generate();
+    /// ```
+    pub fn free_form(mut self, path: impl Into<String>, form: FreeForm) -> Self {
+        self.options = self.options.free_form(path, form);
+        self
+    }
+
+    /// Uses the Rust type at `rust_type` instead of generating the protocol type
+    /// `Domain.Type`, see [`Options::override_type`]:
+    ///
+    /// ```no_run
+    /// auto_generate_cdp::Generator::new()
+    ///     .override_type("Network.Headers", "crate::headers::Headers")
+    ///     .override_type("DOM.Rect", "crate::geometry::Rect")
+    ///     .generate();
+    /// ```
+    pub fn override_type(mut self, path: impl Into<String>, rust_type: impl Into<String>) -> Self {
+        self.options = self.options.override_type(path, rust_type);
+        self
+    }
+
+    /// File to write the bindings to, `$OUT_DIR/protocol.rs` by default.
+    pub fn out_file<P: Into<PathBuf>>(mut self, out_file: P) -> Self {
+        self.out_file = Some(out_file.into());
+        self
+    }
+
+    /// How to format the output. Defaults to [`Format::Pretty`] with the `pretty`
+    /// feature and [`Format::Rustfmt`] without it, or [`Format::Plain`] if
+    /// `DO_NOT_FORMAT` is set.
+    pub fn format(mut self, format: Format) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// How to split the output into files, [`Layout::SingleFile`] by default.
+    pub fn layout(mut self, layout: Layout) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    /// Writes the bindings from a build script, telling cargo what to rerun it for and
+    /// reporting problems as cargo warnings.
+    pub fn generate(&self) {
+        println!("cargo:rerun-if-env-changed=DO_NOT_FORMAT");
+        println!("cargo:rerun-if-env-changed=RUSTFMT");
+        if let Source::Commit(_) = self.source {
+            for file_name in self.preset.files() {
+                print_rerun_directives(file_name);
+            }
+        }
+
+        let inputs = self.read_inputs();
+        for file in &inputs.files {
+            println!("cargo:rerun-if-changed={}", file.display());
+        }
+        self.write_inputs(&inputs, Report::Cargo);
+    }
+
+    /// Writes the bindings like [`generate`](Self::generate) but outside of a build
+    /// script, e.g. from a tool: nothing is printed to stdout and problems are
+    /// reported on stderr.
+    pub fn write(&self) {
+        self.write_inputs(&self.read_inputs(), Report::Stderr);
+    }
+
+    /// Writes the bindings generated from `inputs` unless the output is up to date.
+    fn write_inputs(&self, inputs: &Inputs, report: Report) {
+        let out_file = match &self.out_file {
+            Some(out_file) => out_file.clone(),
+            None => {
+                Path::new(&env::var_os("OUT_DIR").expect("OUT_DIR is not set")).join("protocol.rs")
+            }
+        };
+
+        let header = self.header(inputs);
+
+        let cdp_dir = out_file.with_file_name("cdp");
+
+        if self.up_to_date(&out_file, &cdp_dir, &header) {
+            return;
+        }
+
+        let modules = self.compile(inputs);
+        report.warn_about(&modules);
+
+        let entry = match self.layout {
+            Layout::SingleFile => codegen::cdp_module(&modules, &self.options),
+            Layout::PerDomain => {
+                self.write_modules(&cdp_dir, "mod.rs", &modules, &header, false, report);
+
+                // `include!` resolves paths relative to the including file, so
+                // the module has to be found through an absolute one
+                let mod_rs = cdp_dir.join("mod.rs");
+                let mod_rs = mod_rs.to_str().expect("output path is not valid UTF-8");
+
+                quote! {
+                    #[path = #mod_rs]
+                    pub mod cdp;
+                }
+            }
+        };
+
+        write_file(
+            &out_file,
+            header + &format(&entry, self.format, |err| report.warn(err)),
+        );
+    }
+
+    /// Whether `out_file` and, with [`Layout::PerDomain`], every file of `cdp_dir`
+    /// start with `header`. `mod.rs` lists the other files after its header.
+    fn up_to_date(&self, out_file: &Path, cdp_dir: &Path, header: &str) -> bool {
+        let generated = |path: &Path| -> Option<String> {
+            fs::read_to_string(path)
+                .ok()
+                .filter(|contents| contents.starts_with(header))
+        };
+
+        if generated(out_file).is_none() {
+            return false;
+        }
+        if self.layout == Layout::SingleFile {
+            return true;
+        }
+
+        let mod_rs = match generated(&cdp_dir.join("mod.rs")) {
+            Some(mod_rs) => mod_rs,
+            None => return false,
+        };
+        let files = mod_rs[header.len()..]
+            .lines()
+            .next()
+            .and_then(|line| line.strip_prefix(FILES));
+        match files {
+            Some(files) => files
+                .split_whitespace()
+                .all(|file_name| generated(&cdp_dir.join(file_name)).is_some()),
+            None => false,
+        }
+    }
+
+    /// Generates the `cdp` module without writing it anywhere or printing cargo
+    /// directives, e.g. for a procedural macro.
+    pub fn to_tokens(&self) -> TokenStream {
+        self.to_tokens_with_files().0
+    }
+
+    /// Like [`to_tokens`](Self::to_tokens), also returning every local file read,
+    /// including the ones `.pdl` files include, so that a procedural macro can track
+    /// them.
+    pub fn to_tokens_with_files(&self) -> (TokenStream, Vec<PathBuf>) {
+        let inputs = self.read_inputs();
+        let tokens = codegen::cdp_module(&self.compile(&inputs), &self.options);
+        (tokens, inputs.files)
+    }
+
+    /// Writes a crate named `name` to `dir`, with a `lib.rs` and a module per domain
+    /// in `src` and a feature per domain in `Cargo.toml`, all of them enabled by
+    /// default. Both `Cargo.toml` and `src` are replaced, and problems are reported on
+    /// stderr.
+    pub fn generate_crate<P: AsRef<Path>>(&self, dir: P, name: &str, version: &str) {
+        let dir = dir.as_ref();
+
+        let inputs = self.read_inputs();
+        let modules = self.compile(&inputs);
+        Report::Stderr.warn_about(&modules);
+
+        fs::create_dir_all(dir).unwrap();
+        self.write_modules(
+            &dir.join("src"),
+            "lib.rs",
+            &modules,
+            &self.header(&inputs),
+            true,
+            Report::Stderr,
+        );
+
+        let domains: Vec<String> = modules
+            .iter()
+            .map(|module| format!("{:?}", module.domain))
+            .collect();
+        let features: String = modules
+            .iter()
+            .map(|module| {
+                let dependencies: Vec<String> = module
+                    .dependencies
+                    .iter()
+                    .map(|dep| format!("{:?}", dep))
+                    .collect();
+                format!("{} = [{}]\n", module.domain, dependencies.join(", "))
+            })
+            .collect();
+
+        let time_dependencies: String = self
+            .options
+            .time_crates
+            .iter()
+            .map(|time_crate| match time_crate {
+                TimeCrate::Chrono => "chrono = \"0.4\"\n",
+                TimeCrate::Time => "time = \"0.3\"\n",
+            })
+            .collect();
+
+        let serde_json = if self.options.has_raw_events() {
+            "{ version = \"1\", features = [\"raw_value\"] }"
+        } else {
+            "\"1\""
+        };
+
+        let manifest = format!(
+            "[package]\n\
+             name = {:?}\n\
+             version = {:?}\n\
+             edition = \"2018\"\n\
+             description = {:?}\n\
+             \n\
+             [dependencies]\n\
+             serde = {{ version = \"1\", features = [\"derive\"] }}\n\
+             serde_json = {}\n\
+             {}\
+             \n\
+             [features]\n\
+             default = [{}]\n\
+             {}",
+            name,
+            version,
+            format!(
+                "Chrome DevTools Protocol bindings generated from {}",
+                self.origin()
+            ),
+            serde_json,
+            time_dependencies,
+            domains.join(", "),
+            features
+        );
+        write_file(&dir.join("Cargo.toml"), manifest);
+    }
+
+    /// Writes `root` (e.g. `mod.rs`), `types.rs` and a file per domain to `dir`,
+    /// gating every domain behind a feature of the same name if `features` is set.
+    fn write_modules(
+        &self,
+        dir: &Path,
+        root: &str,
+        modules: &[DomainModule],
+        header: &str,
+        features: bool,
+        report: Report,
+    ) {
+        // generated into a fresh directory so that no files of removed domains are
+        // left behind
+        let tmp_dir = dir.with_extension("tmp");
+        if tmp_dir.exists() {
+            fs::remove_dir_all(&tmp_dir).unwrap();
+        }
+        fs::create_dir_all(&tmp_dir).unwrap();
+
+        let write = |file_name: &str, header: &str, tokens: &TokenStream| {
+            let contents =
+                header.to_string() + &format(tokens, self.format, |err| report.warn(err));
+            fs::write(tmp_dir.join(file_name), contents).unwrap();
+        };
+
+        let cfgs: Vec<TokenStream> = modules
+            .iter()
+            .map(|module| {
+                let domain = &module.domain;
+                if features {
+                    quote! { #[cfg(feature = #domain)] }
+                } else {
+                    quote! {}
+                }
+            })
+            .collect();
+
+        let methods: Vec<TokenStream> = modules
+            .iter()
+            .zip(&cfgs)
+            .flat_map(|(module, cfg)| {
+                module
+                    .methods
+                    .iter()
+                    .map(move |method| quote! { #cfg #method })
+            })
+            .collect();
+        let methods = codegen::methods(&methods);
+        let file_names: Vec<String> = modules
+            .iter()
+            .map(|module| format!("{}.rs", module.name.to_string().trim_start_matches("r#")))
+            .collect();
+        let names = modules.iter().map(|module| &module.name);
+        write(
+            root,
+            &format!("{}{}types.rs {}\n", header, FILES, file_names.join(" ")),
+            &quote! {
+                #![allow(unused)]
+                #![allow(non_camel_case_types)]
+                #![allow(non_snake_case)]
+
+                pub mod types;
+                #(#cfgs pub mod #names;)*
+
+                #methods
+            },
+        );
+
+        let events: Vec<TokenStream> = modules
+            .iter()
+            .zip(&cfgs)
+            .flat_map(|(module, cfg)| {
+                module
+                    .events
+                    .iter()
+                    .map(move |event| quote! { #cfg #event })
+            })
+            .collect();
+        write(
+            "types.rs",
+            header,
+            &codegen::types_module(&events, &self.options),
+        );
+
+        for (module, file_name) in modules.iter().zip(&file_names) {
+            write(file_name, header, &module.body);
+        }
+
+        if dir.exists() {
+            fs::remove_dir_all(dir).unwrap();
+        }
+        fs::rename(&tmp_dir, dir).unwrap();
+    }
+
+    /// Where the protocol comes from, for the comments at the top of the output.
+    fn origin(&self) -> String {
+        match &self.source {
+            Source::Commit(commit) => {
+                let origin = format!("ChromeDevTools/devtools-protocol at commit {}", commit);
+                match self.preset {
+                    Preset::Chrome => origin,
+                    Preset::Node => origin + " and Node's inspector protocol",
+                }
+            }
+            Source::Files(files) => {
+                let files: Vec<String> = files
+                    .iter()
+                    .map(|file| file.display().to_string())
+                    .collect();
+                files.join(", ")
+            }
+            Source::Endpoint(url) => url.clone(),
+        }
+    }
+
+    fn header(&self, inputs: &Inputs) -> String {
+        format!(
+            "// Auto-generated from {}\n// Fingerprint: {:016x}\n",
+            self.origin(),
+            self.fingerprint(inputs)
+        )
+    }
+
+    /// Generates the modules of the selected domains.
+    fn compile(&self, inputs: &Inputs) -> Vec<DomainModule> {
+        let mut protocols: Vec<Value> = inputs
+            .protocols
+            .iter()
+            .map(|json| serde_json::from_str(json).unwrap())
+            .collect();
+
+        if let Err(errors) = overlay::apply(&mut protocols, &inputs.overlays) {
+            panic!("conflicting protocol definitions:\n{}", errors.join("\n"));
+        }
+
+        let protocols: Vec<Protocol> = protocols
+            .into_iter()
+            .map(|protocol| serde_json::from_value(protocol).unwrap())
+            .collect();
+
+        codegen::select(
+            codegen::domains_with(&protocols, &self.options),
+            &self.domains,
+        )
+    }
+
+    /// Reads the protocols of the source, the extra files and the overlays.
+    fn read_inputs(&self) -> Inputs {
+        let mut inputs = Inputs {
+            protocols: Vec::new(),
+            overlays: Vec::new(),
+            files: Vec::new(),
+        };
+
+        match &self.source {
+            Source::Commit(commit) => {
+                for file_name in self.preset.files() {
+                    inputs.protocols.push(fetch_json(file_name, commit));
+                }
+                if self.preset == Preset::Node {
+                    inputs.protocols.push(NODE_PROTOCOL.to_string());
+                }
+            }
+            Source::Endpoint(url) => inputs.protocols.push(fetch_endpoint(url)),
+            Source::Files(paths) => {
+                for path in paths {
+                    inputs.read_protocol(path);
+                }
+            }
+        }
+
+        for path in &self.extra_files {
+            inputs.read_protocol(path);
+        }
+        for path in &self.overlays {
+            let overlay = read_file(path);
+            inputs.files.push(path.clone());
+            inputs.overlays.push((path.display().to_string(), overlay));
+        }
+
+        inputs
+    }
+
+    /// Hash of everything the output depends on.
+    fn fingerprint(&self, inputs: &Inputs) -> u64 {
+        let mut fingerprint = Fingerprint::new();
+
+        fingerprint.write(env!("CARGO_PKG_VERSION").as_bytes());
+        fingerprint.write(format!("{:?}", self).as_bytes());
+        if cfg!(feature = "cbor") {
+            fingerprint.write(b"cbor");
+        }
+        if self.format == Format::Rustfmt {
+            let rustfmt = env::var("RUSTFMT").unwrap_or_default();
+            fingerprint.write(rustfmt.as_bytes());
+        }
+        for json in &inputs.protocols {
+            fingerprint.write(json.as_bytes());
+        }
+        for (_, overlay) in &inputs.overlays {
+            fingerprint.write(overlay.as_bytes());
+        }
+
+        fingerprint.0
+    }
+}
+
+/// Where problems that don't stop the generation are reported.
+#[derive(Debug, Clone, Copy)]
+enum Report {
+    /// As `cargo:warning` lines, which cargo shows for build scripts.
+    Cargo,
+    /// As plain text on stderr.
+    Stderr,
+}
+
+impl Report {
+    fn warn(self, message: &str) {
+        match self {
+            Report::Cargo => {
+                for line in message.lines() {
+                    println!("cargo:warning={}", line);
+                }
+            }
+            Report::Stderr => eprintln!("warning: {}", message),
+        }
+    }
+
+    /// Reports the warnings of the generated modules.
+    fn warn_about(self, modules: &[DomainModule]) {
+        for warning in modules.iter().flat_map(|module| &module.warnings) {
+            self.warn(warning);
+        }
+    }
+}
+
+/// Everything generated from, as read by [`Generator::read_inputs`].
+struct Inputs {
+    /// The protocols as JSON.
+    protocols: Vec<String>,
+    /// The overlays as JSON, with the name of their file.
+    overlays: Vec<(String, String)>,
+    /// Local files read, for cargo to watch.
+    files: Vec<PathBuf>,
+}
+
+impl Inputs {
+    /// Reads the protocol in `path`. `.pdl` files are converted to JSON, so that the
+    /// fingerprint covers the files they include.
+    fn read_protocol(&mut self, path: &Path) {
+        if path.extension().is_some_and(|extension| extension == "pdl") {
+            let pdl = pdl::parse_file(path).unwrap_or_else(|err| panic!("{}", err));
+            self.protocols
+                .push(serde_json::to_string(&pdl.protocol).unwrap());
+            self.files.extend(pdl.files);
+        } else {
+            self.protocols.push(read_file(path));
+            self.files.push(path.to_path_buf());
+        }
+    }
+}
+
+fn read_file(path: &Path) -> String {
+    fs::read_to_string(path)
+        .unwrap_or_else(|err| panic!("could not read {}: {}", path.display(), err))
+}
+
+/// Writes next to `path` and renames, so that it is never left half written.
+fn write_file(path: &Path, contents: String) {
+    let tmp_file = path.with_extension("tmp");
+    fs::write(&tmp_file, contents).unwrap();
+    fs::rename(&tmp_file, path).unwrap();
+}
+
+/// 64-bit FNV-1a, which unlike `DefaultHasher` is stable across Rust releases.
+struct Fingerprint(u64);
+
+impl Fingerprint {
+    fn new() -> Self {
+        Fingerprint(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        // terminate every input so that moving bytes between inputs changes the hash
+        for byte in bytes.iter().chain(&[0xff]) {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    #[test]
+    fn fingerprint_covers_inputs() {
+        let inputs = |protocol: &str, overlay: &str| Inputs {
+            protocols: vec![String::from(protocol)],
+            overlays: vec![(String::from("overlay.json"), String::from(overlay))],
+            files: Vec::new(),
+        };
+        let fingerprint = Generator::new()
+            .format(Format::Rustfmt)
+            .fingerprint(&inputs("{}", "[]"));
+
+        assert_eq!(
+            fingerprint,
+            Generator::new()
+                .format(Format::Rustfmt)
+                .fingerprint(&inputs("{}", "[]"))
+        );
+        assert_ne!(
+            fingerprint,
+            Generator::new()
+                .format(Format::Plain)
+                .fingerprint(&inputs("{}", "[]"))
+        );
+        assert_ne!(
+            fingerprint,
+            Generator::new()
+                .commit("main")
+                .format(Format::Rustfmt)
+                .fingerprint(&inputs("{}", "[]"))
+        );
+        assert_ne!(
+            fingerprint,
+            Generator::new()
+                .format(Format::Rustfmt)
+                .fingerprint(&inputs("{ }", "[]"))
+        );
+        assert_ne!(
+            fingerprint,
+            Generator::new()
+                .format(Format::Rustfmt)
+                .fingerprint(&inputs("{}", "[ ]"))
+        );
+    }
+
+    #[test]
+    fn fetches_endpoint() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/json/protocol", listener.local_addr().unwrap());
+
+        let browser = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0; 1024];
+            let len = stream.read(&mut request).unwrap();
+
+            let body = r#"{
+                "version": { "major": "1", "minor": "3" },
+                "domains": [{ "domain": "Browser", "commands": [{ "name": "close" }] }]
+            }"#;
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                body.len(),
+                body
+            )
+            .unwrap();
+
+            String::from_utf8_lossy(&request[..len]).into_owned()
+        });
+
+        let tokens = Generator::new()
+            .source(Source::Endpoint(url))
+            .to_tokens()
+            .to_string();
+
+        assert!(browser
+            .join()
+            .unwrap()
+            .starts_with("GET /json/protocol HTTP/1.1"));
+        assert!(tokens.contains("\"Browser.close\""));
+    }
+
+    #[test]
+    #[cfg(feature = "offline")]
+    fn generates_node_preset() {
+        let tokens = Generator::new()
+            .preset(Preset::Node)
+            .to_tokens()
+            .to_string();
+
+        assert!(tokens.contains("\"NodeWorker.attachedToWorker\""));
+        assert!(tokens.contains("\"Network.requestWillBeSent\""));
+        assert!(tokens.contains("\"Runtime.evaluate\""));
+        assert!(!tokens.contains("\"Page.navigate\""));
+    }
+
+    #[test]
+    fn generates_crates() {
+        let dir = env::temp_dir().join("auto_generate_cdp_crate");
+        if dir.exists() {
+            fs::remove_dir_all(&dir).unwrap();
+        }
+        fs::create_dir_all(&dir).unwrap();
+        let protocol = dir.join("protocol.json");
+        fs::write(
+            &protocol,
+            r#"{
+                "version": { "major": "1", "minor": "3" },
+                "domains": [
+                    { "domain": "DOM", "types": [{ "id": "NodeId", "type": "integer" }], "commands": [] },
+                    { "domain": "Overlay", "commands": [
+                        { "name": "highlightNode", "parameters": [{ "name": "nodeId", "$ref": "DOM.NodeId" }] },
+                        { "name": "hideHighlight" }
+                    ] },
+                    { "domain": "Page", "commands": [{ "name": "hideHighlight", "redirect": "Overlay" }] }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let out = dir.join("cdp-bindings");
+        Generator::new()
+            .files([&protocol])
+            .format(Format::Plain)
+            .generate_crate(&out, "cdp-bindings", "1.2.3");
+
+        let manifest = fs::read_to_string(out.join("Cargo.toml")).unwrap();
+        assert!(manifest.starts_with("[package]\nname = \"cdp-bindings\"\nversion = \"1.2.3\"\n"));
+        assert!(manifest.contains("\nserde_json = \"1\"\n"));
+        let features = &manifest[manifest.find("[features]\n").unwrap()..];
+        assert_eq!(
+            features,
+            "[features]\n\
+             default = [\"DOM\", \"Overlay\", \"Page\"]\n\
+             DOM = []\n\
+             Overlay = [\"DOM\"]\n\
+             Page = [\"Overlay\"]\n"
+        );
+
+        let lib: String = fs::read_to_string(out.join("src").join("lib.rs"))
+            .unwrap()
+            .split_whitespace()
+            .collect();
+        assert!(lib.contains("#[cfg(feature=\"Overlay\")]pubmodOverlay;"));
+        for file_name in ["types.rs", "DOM.rs", "Overlay.rs", "Page.rs"] {
+            assert!(out.join("src").join(file_name).exists());
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "offline")]
+    fn generates_per_domain_layout() {
+        let dir = env::temp_dir().join("auto_generate_cdp_per_domain");
+        if dir.exists() {
+            fs::remove_dir_all(&dir).unwrap();
+        }
+        fs::create_dir_all(&dir).unwrap();
+        let generator = Generator::new()
+            .domains(["Page"])
+            .format(Format::Plain)
+            .layout(Layout::PerDomain)
+            .out_file(dir.join("protocol.rs"));
+        generator.write();
+
+        // the spacing of punctuation differs between parsed and generated tokens
+        let compact =
+            |tokens: TokenStream| -> String { tokens.to_string().split_whitespace().collect() };
+        let tokens = |path: &Path| -> String {
+            let source = fs::read_to_string(path)
+                .unwrap_or_else(|err| panic!("could not read {}: {}", path.display(), err));
+            compact(source.parse().unwrap())
+        };
+        let cdp_dir = dir.join("cdp");
+        let mod_rs = cdp_dir.join("mod.rs");
+        let mod_rs_path = mod_rs.to_str().unwrap();
+
+        // the entry point includes the directory through an absolute path
+        assert_eq!(
+            tokens(&dir.join("protocol.rs")),
+            compact(quote! {
+                #[path = #mod_rs_path]
+                pub mod cdp;
+            })
+        );
+        assert!(!dir.join("cdp.tmp").exists());
+
+        let modules = generator.compile(&generator.read_inputs());
+        let root = tokens(&mod_rs);
+        assert!(root.contains(&compact(quote! { pub mod types; })));
+        assert!(root.contains("pubstaticMETHODS"));
+        assert!(modules.iter().any(|module| module.domain == "Network"));
+        for module in &modules {
+            let name = &module.name;
+            let file_name = format!("{}.rs", name.to_string().trim_start_matches("r#"));
+            assert!(root.contains(&compact(quote! { pub mod #name; })));
+            assert_eq!(
+                tokens(&cdp_dir.join(file_name)),
+                compact(module.body.clone())
+            );
+        }
+
+        let events = modules.iter().flat_map(|module| &module.events);
+        assert_eq!(
+            tokens(&cdp_dir.join("types.rs")),
+            compact(codegen::types_module(events, &generator.options))
+        );
+
+        // every file is checked, not just the entry point
+        let header = generator.header(&generator.read_inputs());
+        let out_file = dir.join("protocol.rs");
+        assert!(generator.up_to_date(&out_file, &cdp_dir, &header));
+        fs::remove_file(cdp_dir.join("Page.rs")).unwrap();
+        assert!(!generator.up_to_date(&out_file, &cdp_dir, &header));
+        generator.write();
+        assert!(generator.up_to_date(&out_file, &cdp_dir, &header));
+        fs::write(cdp_dir.join("types.rs"), "// edited\n").unwrap();
+        assert!(!generator.up_to_date(&out_file, &cdp_dir, &header));
+    }
+}
@@ -0,0 +1,211 @@
+use std::collections::HashMap;
+
+use convert_case::{Case, Casing};
+
+use proc_macro2::{Ident, Span};
+
+use crate::types::{Parameter, Protocol};
+
+/// Keywords (strict, reserved and edition specific) that need `r#` to be used
+/// as identifiers.
+const KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "do", "dyn",
+    "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl", "in", "let",
+    "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return",
+    "static", "struct", "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use",
+    "virtual", "where", "while", "yield",
+];
+
+/// Keywords that can't be written as raw identifiers.
+const RESERVED: &[&str] = &["crate", "self", "Self", "super", "_"];
+
+/// Rust name of a struct field for the protocol name `name`, e.g. `nodeId` becomes
+/// `node_id` and `type` becomes `r#type`.
+pub fn field_name(name: &str) -> String {
+    let name = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect::<String>()
+        .to_case(Case::Snake);
+
+    if RESERVED.contains(&name.as_str()) {
+        name + "_"
+    } else if name.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("_{}", name)
+    } else {
+        name
+    }
+}
+
+/// Rust name of a type, module or enum variant for the protocol name `name`.
+/// Names containing separators (e.g. `no-referrer`) are joined in Pascal case.
+pub fn type_name(name: &str) -> String {
+    let name = if name.contains(|c: char| !c.is_ascii_alphanumeric()) {
+        name.split(|c: char| !c.is_ascii_alphanumeric())
+            .map(|s| {
+                let mut chars = s.chars();
+                match chars.next() {
+                    Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+                    None => String::new(),
+                }
+            })
+            .collect::<String>()
+    } else {
+        name.to_string()
+    };
+
+    if RESERVED.contains(&name.as_str()) || name.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("Cdp{}", name)
+    } else {
+        name
+    }
+}
+
+/// Rust name of an enum variant for the protocol value `value`.
+pub fn variant_name(value: &str) -> String {
+    if value.contains(|c: char| !c.is_ascii_alphanumeric()) {
+        type_name(value)
+    } else {
+        type_name(&value.to_case(Case::Pascal))
+    }
+}
+
+/// Builds an identifier, escaping keywords with `r#`.
+pub fn ident(name: &str) -> Ident {
+    if KEYWORDS.contains(&name) {
+        Ident::new_raw(name, Span::call_site())
+    } else {
+        Ident::new(name, Span::call_site())
+    }
+}
+
+pub fn field_ident(name: &str) -> Ident {
+    ident(&field_name(name))
+}
+
+pub fn type_ident(name: &str) -> Ident {
+    ident(&type_name(name))
+}
+
+pub fn variant_ident(value: &str) -> Ident {
+    ident(&variant_name(value))
+}
+
+/// Tracks the Rust names given out within one struct or enum.
+struct Scope<'a> {
+    context: String,
+    names: HashMap<String, &'a str>,
+}
+
+impl<'a> Scope<'a> {
+    fn new(context: String) -> Self {
+        Scope {
+            context,
+            names: HashMap::new(),
+        }
+    }
+
+    fn insert(&mut self, protocol_name: &'a str, rust_name: String, collisions: &mut Vec<String>) {
+        if let Some(previous) = self.names.insert(rust_name.clone(), protocol_name) {
+            if previous != protocol_name {
+                collisions.push(format!(
+                    "{}: `{}` and `{}` both map to `{}`",
+                    self.context, previous, protocol_name, rust_name
+                ));
+            }
+        }
+    }
+}
+
+fn check_enum(context: String, values: &[String], collisions: &mut Vec<String>) {
+    let mut scope = Scope::new(context);
+
+    for value in values {
+        scope.insert(value, variant_name(value), collisions);
+    }
+}
+
+fn check_fields(context: String, fields: &[Parameter], collisions: &mut Vec<String>) {
+    let mut scope = Scope::new(context.clone());
+
+    for field in fields {
+        scope.insert(&field.name, field_name(&field.name), collisions);
+
+        if let Some(values) = &field.parameter_enum {
+            check_enum(format!("{}.{}", context, field.name), values, collisions);
+        }
+    }
+}
+
+/// Panics if two protocol names of the same struct or enum map to the same Rust
+/// identifier, listing every collision found in `protocol`.
+pub fn check_collisions(protocol: &Protocol) {
+    let mut collisions = Vec::new();
+
+    for dom in &protocol.domains {
+        for type_element in dom.types.as_deref().unwrap_or_default() {
+            let context = format!("{}.{}", dom.domain, type_element.id);
+
+            if let Some(properties) = &type_element.properties {
+                check_fields(context.clone(), properties, &mut collisions);
+            }
+            if let Some(values) = &type_element.type_enum {
+                check_enum(context, values, &mut collisions);
+            }
+        }
+
+        for command in &dom.commands {
+            let context = format!("{}.{}", dom.domain, command.name);
+
+            if let Some(parameters) = &command.parameters {
+                check_fields(context.clone(), parameters, &mut collisions);
+            }
+            if let Some(returns) = &command.returns {
+                check_fields(format!("{} (returns)", context), returns, &mut collisions);
+            }
+        }
+
+        for event in dom.events.as_deref().unwrap_or_default() {
+            if let Some(parameters) = &event.parameters {
+                let context = format!("{}.{}", dom.domain, event.name);
+                check_fields(context, parameters, &mut collisions);
+            }
+        }
+    }
+
+    if !collisions.is_empty() {
+        panic!(
+            "protocol names collide after conversion to Rust identifiers:\n{}",
+            collisions.join("\n")
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sanitizes_identifiers() {
+        assert_eq!(field_ident("type").to_string(), "r#type");
+        assert_eq!(field_ident("contentType").to_string(), "content_type");
+        assert_eq!(field_ident("override").to_string(), "r#override");
+        assert_eq!(field_ident("self").to_string(), "self_");
+        assert_eq!(field_ident("100").to_string(), "_100");
+        assert_eq!(variant_ident("no-referrer").to_string(), "NoReferrer");
+        assert_eq!(variant_ident("self").to_string(), "CdpSelf");
+        assert_eq!(variant_ident("2d").to_string(), "Cdp2D");
+    }
+
+    #[test]
+    fn reports_collisions() {
+        let values = vec![String::from("foo-bar"), String::from("fooBar")];
+        let mut collisions = Vec::new();
+        check_enum(String::from("Test.Enum"), &values, &mut collisions);
+
+        assert_eq!(
+            collisions,
+            vec!["Test.Enum: `foo-bar` and `fooBar` both map to `FooBar`"]
+        );
+    }
+}
@@ -0,0 +1,263 @@
+//! Which generated types borrow from the input in [`Options::borrowed`] mode, where
+//! strings are `Cow<'a, str>` and JSON values `&'a RawValue`.
+
+use std::collections::HashSet;
+
+use proc_macro2::{Ident, TokenStream};
+use quote::quote;
+
+use crate::attributes::is_field;
+use crate::codegen::Options;
+use crate::free_form::FreeFormObject;
+use crate::symbols::SymbolTable;
+use crate::types::{Command, Event, Items, Parameter, Protocol, TypeElement, TypeEnum};
+
+/// Whether a field holds strings, which serde only borrows for bare `Cow<'a, str>`s.
+enum Strings {
+    One,
+    Many,
+}
+
+pub struct Lifetimes<'a> {
+    borrowed: bool,
+    options: &'a Options,
+    symbols: &'a SymbolTable<'a>,
+    /// The protocol types that take a lifetime, as `Domain.Type`.
+    types: HashSet<String>,
+}
+
+impl<'a> Lifetimes<'a> {
+    pub fn new(
+        protocols: &'a [Protocol],
+        symbols: &'a SymbolTable<'a>,
+        options: &'a Options,
+    ) -> Self {
+        let mut lifetimes = Lifetimes {
+            borrowed: options.borrowed,
+            options,
+            symbols,
+            types: HashSet::new(),
+        };
+        if !lifetimes.borrowed {
+            return lifetimes;
+        }
+
+        let elements: Vec<(&str, &TypeElement)> = protocols
+            .iter()
+            .flat_map(|protocol| &protocol.domains)
+            .flat_map(|dom| {
+                let domain = dom.domain.as_str();
+                dom.types
+                    .iter()
+                    .flatten()
+                    .map(move |element| (domain, element))
+            })
+            // the types replacing protocol types own their data
+            .filter(|(domain, element)| !symbols.is_overridden(domain, &element.id))
+            .collect();
+
+        // a type takes a lifetime once one of the types it refers to does
+        loop {
+            let borrowing: Vec<String> = elements
+                .iter()
+                .filter_map(|(domain, element)| {
+                    let key = format!("{}.{}", domain, element.id);
+                    if !lifetimes.types.contains(&key) && lifetimes.type_borrows(domain, element) {
+                        Some(key)
+                    } else {
+                        None
+                    }
+                })
+                .collect();
+
+            if borrowing.is_empty() {
+                break;
+            }
+            lifetimes.types.extend(borrowing);
+        }
+
+        lifetimes
+    }
+
+    pub fn borrowed(&self) -> bool {
+        self.borrowed
+    }
+
+    /// `<'a>` if the item takes a lifetime.
+    pub fn generics(&self, borrows: bool) -> TokenStream {
+        if borrows {
+            quote! { <'a> }
+        } else {
+            quote! {}
+        }
+    }
+
+    /// Whether the type `reference` refers to from `domain` takes a lifetime.
+    pub fn of_ref(&self, domain: &str, reference: &str) -> bool {
+        let symbol = self.symbols.resolve(domain, reference).unwrap();
+        self.types
+            .contains(&format!("{}.{}", symbol.domain, symbol.element.id))
+    }
+
+    /// Whether a struct with `fields` takes a lifetime.
+    pub fn of_fields(&self, domain: &str, fields: &[Parameter]) -> bool {
+        fields.iter().any(|field| self.field_borrows(domain, field))
+    }
+
+    /// Whether a struct wrapping an optional JSON value takes a lifetime.
+    pub fn of_json(&self) -> bool {
+        self.borrowed
+    }
+
+    /// Whether the parameters of `command` take a lifetime.
+    pub fn of_parameters(&self, domain: &str, command: &Command) -> bool {
+        match command.parameters.as_deref() {
+            Some(parameters) => self.of_fields(domain, parameters),
+            None => self.of_json(),
+        }
+    }
+
+    /// Whether the return object of `command` takes a lifetime.
+    pub fn of_returns(&self, domain: &str, command: &Command) -> bool {
+        command
+            .returns
+            .as_deref()
+            .is_some_and(|returns| self.of_fields(domain, returns))
+    }
+
+    /// Whether `event` takes a lifetime.
+    pub fn of_event(&self, domain: &str, event: &Event) -> bool {
+        match event.parameters.as_deref() {
+            Some(parameters) => self.of_fields(domain, parameters),
+            None => self.of_json(),
+        }
+    }
+
+    /// The field of a struct wrapping an optional JSON value.
+    pub fn json_field(&self) -> TokenStream {
+        if self.borrowed {
+            quote! { #[serde(borrow)] pub Option<&'a RawValue> }
+        } else {
+            quote! { pub Option<serde_json::Value> }
+        }
+    }
+
+    /// Rust type of a JSON type, `None` for objects and arrays, see [`FreeFormObject`].
+    pub fn primitive(&self, type_type: TypeEnum) -> Option<TokenStream> {
+        match type_type {
+            TypeEnum::String if self.borrowed => Some(quote! { Cow<'a, str> }),
+            TypeEnum::Any if self.borrowed => Some(quote! { &'a RawValue }),
+            type_type => {
+                let typ: Option<Ident> = type_type.into();
+                typ.map(|typ| quote! { #typ })
+            }
+        }
+    }
+
+    /// The serde attributes that make `field` borrow from the input. Optional fields
+    /// with them need `#[serde(default)]`, which `deserialize_with` turns off.
+    pub fn field_attributes(&self, domain: &str, field: &Parameter) -> TokenStream {
+        if !self.field_borrows(domain, field) {
+            return quote! {};
+        }
+
+        let optional = field.optional.unwrap_or(false);
+        let deserialize_with = match (self.strings(domain, field), optional) {
+            (Some(Strings::One), false) => "borrow::cow_str",
+            (Some(Strings::One), true) => "borrow::option_cow_str",
+            (Some(Strings::Many), false) => "borrow::vec_cow_str",
+            (Some(Strings::Many), true) => "borrow::option_vec_cow_str",
+            (None, _) => return quote! { #[serde(borrow)] },
+        };
+
+        quote! { #[serde(borrow, deserialize_with = #deserialize_with)] }
+    }
+
+    fn type_borrows(&self, domain: &str, element: &TypeElement) -> bool {
+        match element.type_type {
+            TypeEnum::Array => self.items_borrow(domain, element.items.as_ref()),
+            TypeEnum::Object => {
+                let properties = element.properties.as_deref().unwrap_or_default();
+                if properties.iter().any(is_field) {
+                    self.of_fields(domain, properties)
+                } else {
+                    FreeFormObject::of_type(self.options, domain, &element.id).borrows(self)
+                }
+            }
+            TypeEnum::String => element.type_enum.is_none(),
+            TypeEnum::Any => true,
+            TypeEnum::Boolean | TypeEnum::Integer | TypeEnum::Number => false,
+        }
+    }
+
+    fn field_borrows(&self, domain: &str, field: &Parameter) -> bool {
+        if !self.borrowed {
+            return false;
+        }
+        if let Some(reference) = &field.parameter_ref {
+            return self.of_ref(domain, reference);
+        }
+
+        match field.parameter_type {
+            Some(TypeEnum::Array) => self.items_borrow(domain, field.items.as_ref()),
+            Some(TypeEnum::String) => field.parameter_enum.is_none(),
+            // inline objects are never headers, so their values are JSON
+            Some(TypeEnum::Any) | Some(TypeEnum::Object) => true,
+            _ => false,
+        }
+    }
+
+    fn items_borrow(&self, domain: &str, items: Option<&Items>) -> bool {
+        match items {
+            Some(Items {
+                items_ref: Some(reference),
+                ..
+            }) => self.of_ref(domain, reference),
+            Some(Items {
+                items_type: Some(items_type),
+                ..
+            }) => matches!(
+                items_type,
+                TypeEnum::String | TypeEnum::Any | TypeEnum::Object
+            ),
+            _ => false,
+        }
+    }
+
+    /// Whether `field` is a string or a list of them, through type aliases too.
+    fn strings(&self, domain: &str, field: &Parameter) -> Option<Strings> {
+        let is_string = |domain: &str, reference: &str| {
+            let element = self.symbols.resolve(domain, reference).unwrap().element;
+            matches!(element.type_type, TypeEnum::String) && element.type_enum.is_none()
+        };
+        let are_strings = |domain: &str, items: Option<&Items>| match items {
+            Some(Items {
+                items_ref: Some(reference),
+                ..
+            }) => is_string(domain, reference),
+            Some(Items { items_type, .. }) => matches!(items_type, Some(TypeEnum::String)),
+            None => false,
+        };
+
+        if let Some(reference) = &field.parameter_ref {
+            let symbol = self.symbols.resolve(domain, reference).unwrap();
+            return if is_string(domain, reference) {
+                Some(Strings::One)
+            } else if matches!(symbol.element.type_type, TypeEnum::Array)
+                && are_strings(symbol.domain, symbol.element.items.as_ref())
+            {
+                Some(Strings::Many)
+            } else {
+                None
+            };
+        }
+
+        match field.parameter_type {
+            Some(TypeEnum::String) if field.parameter_enum.is_none() => Some(Strings::One),
+            Some(TypeEnum::Array) if are_strings(domain, field.items.as_ref()) => {
+                Some(Strings::Many)
+            }
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,244 @@
+//! JSON-patch-style overlays that change the protocol before it is generated, e.g.
+//! for the extra domains and parameters of a Chromium fork.
+//!
+//! An overlay is a JSON array of operations:
+//!
+//! ```json
+//! [
+//!     { "op": "add", "path": "/domains/Page/commands/navigate/parameters/acmeMode",
+//!       "value": { "name": "acmeMode", "type": "boolean", "optional": true } },
+//!     { "op": "replace", "path": "/domains/Page/types/FrameId",
+//!       "value": { "id": "FrameId", "type": "integer" } },
+//!     { "op": "remove", "path": "/domains/Page/types/TransitionType/enum/typed" }
+//! ]
+//! ```
+//!
+//! Paths name list elements instead of indexing them: domains by `domain`, types by
+//! `id`, commands, events and fields by `name` and enum values by themselves.
+
+use std::collections::HashSet;
+
+use serde::Deserialize;
+use serde_json::Value;
+
+#[derive(Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+enum Operation {
+    Add { path: String, value: Value },
+    Replace { path: String, value: Value },
+    Remove { path: String },
+}
+
+/// Applies `overlays` (a name for errors and the JSON) to `protocols` in order and
+/// checks that no domain is defined twice, returning every conflict found.
+pub fn apply(protocols: &mut [Value], overlays: &[(String, String)]) -> Result<(), Vec<String>> {
+    let mut errors = Vec::new();
+
+    for (name, overlay) in overlays {
+        let operations: Vec<Operation> = match serde_json::from_str(overlay) {
+            Ok(operations) => operations,
+            Err(err) => {
+                errors.push(format!("{}: {}", name, err));
+                continue;
+            }
+        };
+
+        for operation in operations {
+            if let Err(err) = apply_operation(protocols, operation) {
+                errors.push(format!("{}: {}", name, err));
+            }
+        }
+    }
+
+    let mut domains = HashSet::new();
+    for protocol in protocols.iter() {
+        for domain in protocol["domains"].as_array().into_iter().flatten() {
+            let domain = key(domain).unwrap_or_default();
+            if !domains.insert(domain) {
+                errors.push(format!(
+                    "domain `{}` is defined more than once, remove all but one with an overlay",
+                    domain
+                ));
+            }
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+fn apply_operation(protocols: &mut [Value], operation: Operation) -> Result<(), String> {
+    let (path, value) = match &operation {
+        Operation::Add { path, value } | Operation::Replace { path, value } => (path, Some(value)),
+        Operation::Remove { path } => (path, None),
+    };
+    let path = path.clone();
+
+    let segments: Vec<&str> = match path.strip_prefix("/domains/") {
+        Some(rest) => rest.split('/').collect(),
+        None => return Err(format!("`{}` does not start with `/domains/`", path)),
+    };
+    let (name, parents) = segments.split_last().unwrap();
+
+    if let Some(value) = value {
+        if key(value) != Some(name) {
+            return Err(format!("the value for `{}` must be named `{}`", path, name));
+        }
+    }
+
+    // the protocol that defines the domain, new domains are added to the last one
+    let protocol = protocols
+        .iter()
+        .position(|protocol| find(&protocol["domains"], segments[0]).is_some())
+        .or_else(|| protocols.len().checked_sub(1))
+        .ok_or_else(|| String::from("there is no protocol to apply overlays to"))?;
+
+    let add = matches!(operation, Operation::Add { .. });
+    let list = list(&mut protocols[protocol]["domains"], parents, add)
+        .ok_or_else(|| format!("the parent of `{}` does not exist", path))?;
+    let position = list.iter().position(|element| key(element) == Some(name));
+
+    match (operation, position) {
+        (Operation::Add { .. }, Some(_)) => Err(format!(
+            "`{}` already exists, use `replace` to change it",
+            path
+        )),
+        (Operation::Add { value, .. }, None) => {
+            list.push(value);
+            Ok(())
+        }
+        (Operation::Replace { value, .. }, Some(position)) => {
+            list[position] = value;
+            Ok(())
+        }
+        (Operation::Remove { .. }, Some(position)) => {
+            list.remove(position);
+            Ok(())
+        }
+        (_, None) => Err(format!("`{}` does not exist", path)),
+    }
+}
+
+/// The list that `segments` lead to from `value`, creating it if `create` is set and
+/// only the list itself is missing.
+fn list<'a>(
+    mut value: &'a mut Value,
+    segments: &[&str],
+    create: bool,
+) -> Option<&'a mut Vec<Value>> {
+    for (index, segment) in segments.iter().enumerate() {
+        value = match value {
+            Value::Object(object) => {
+                if create && index == segments.len() - 1 {
+                    object
+                        .entry(segment.to_string())
+                        .or_insert_with(|| Value::Array(Vec::new()))
+                } else {
+                    object.get_mut(*segment)?
+                }
+            }
+            Value::Array(elements) => elements
+                .iter_mut()
+                .find(|element| key(element) == Some(segment))?,
+            _ => return None,
+        };
+    }
+
+    value.as_array_mut()
+}
+
+fn find<'a>(list: &'a Value, name: &str) -> Option<&'a Value> {
+    list.as_array()?
+        .iter()
+        .find(|element| key(element) == Some(name))
+}
+
+/// What a list element is called in paths.
+fn key(element: &Value) -> Option<&str> {
+    match element {
+        Value::String(value) => Some(value),
+        Value::Object(object) => ["domain", "id", "name"]
+            .iter()
+            .find_map(|key| object.get(*key)?.as_str()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::json;
+
+    fn protocol() -> Value {
+        json!({
+            "version": { "major": "1", "minor": "3" },
+            "domains": [{
+                "domain": "Page",
+                "types": [
+                    { "id": "FrameId", "type": "string" },
+                    { "id": "TransitionType", "type": "string", "enum": ["link", "typed"] }
+                ],
+                "commands": [{ "name": "navigate", "parameters": [{ "name": "url", "type": "string" }] }]
+            }]
+        })
+    }
+
+    #[test]
+    fn applies_overlays() {
+        let mut protocols = vec![protocol()];
+        let overlay = json!([
+            { "op": "add", "path": "/domains/Acme", "value": { "domain": "Acme", "commands": [] } },
+            { "op": "add", "path": "/domains/Page/commands/navigate/parameters/acmeMode",
+              "value": { "name": "acmeMode", "type": "boolean", "optional": true } },
+            { "op": "add", "path": "/domains/Page/commands/navigate/returns/acmeId",
+              "value": { "name": "acmeId", "type": "string" } },
+            { "op": "replace", "path": "/domains/Page/types/FrameId",
+              "value": { "id": "FrameId", "type": "integer" } },
+            { "op": "remove", "path": "/domains/Page/types/TransitionType/enum/typed" }
+        ]);
+
+        apply(
+            &mut protocols,
+            &[(String::from("acme.json"), overlay.to_string())],
+        )
+        .unwrap();
+
+        let page = &protocols[0]["domains"][0];
+        assert_eq!(protocols[0]["domains"][1]["domain"], "Acme");
+        assert_eq!(page["commands"][0]["parameters"][1]["name"], "acmeMode");
+        assert_eq!(page["commands"][0]["returns"][0]["name"], "acmeId");
+        assert_eq!(page["types"][0]["type"], "integer");
+        assert_eq!(page["types"][1]["enum"], json!(["link"]));
+    }
+
+    #[test]
+    fn reports_conflicts() {
+        let mut protocols = vec![protocol(), protocol()];
+        let overlay = json!([
+            { "op": "add", "path": "/domains/Page/types/FrameId",
+              "value": { "id": "FrameId", "type": "integer" } },
+            { "op": "remove", "path": "/domains/Page/events/loadEventFired" },
+            { "op": "replace", "path": "/domains/Page/types/FrameId", "value": { "id": "Frame" } }
+        ]);
+
+        let errors = apply(
+            &mut protocols,
+            &[(String::from("acme.json"), overlay.to_string())],
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            errors,
+            vec![
+                "acme.json: `/domains/Page/types/FrameId` already exists, use `replace` to change it",
+                "acme.json: the parent of `/domains/Page/events/loadEventFired` does not exist",
+                "acme.json: the value for `/domains/Page/types/FrameId` must be named `FrameId`",
+                "domain `Page` is defined more than once, remove all but one with an overlay",
+            ]
+        );
+    }
+}
@@ -0,0 +1,591 @@
+//! Parser for the protocol definition language (`.pdl`) Chromium writes the
+//! protocol in, following `third_party/inspector_protocol/pdl.py`.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::cbor::BINARY_NOTE;
+use crate::types::{
+    Command, Domain, Event, Items, Parameter, Protocol, TypeElement, TypeEnum, Version,
+};
+
+/// A protocol parsed from a `.pdl` file and the files it included.
+pub struct Pdl {
+    pub protocol: Protocol,
+    pub files: Vec<PathBuf>,
+}
+
+/// Parses `path` and the files it includes, failing with `file:line: message`.
+pub fn parse_file(path: &Path) -> Result<Pdl, String> {
+    let mut parser = Parser::default();
+    parser.parse_file(path)?;
+
+    let version = parser
+        .version
+        .ok_or_else(|| format!("{}: missing `version`", path.display()))?;
+
+    Ok(Pdl {
+        protocol: Protocol {
+            version: Version {
+                major: version.0,
+                minor: version.1,
+            },
+            domains: parser.domains,
+        },
+        files: parser.files,
+    })
+}
+
+/// The item that `parameters`, `returns`, `properties`, `enum` and `redirect`
+/// lines belong to.
+#[derive(Clone, Copy)]
+enum Item {
+    Type,
+    Command,
+    Event,
+}
+
+#[derive(Clone, Copy)]
+enum Members {
+    Properties,
+    Parameters,
+    Returns,
+}
+
+/// Where enum literals are added.
+#[derive(Clone, Copy)]
+enum Literals {
+    Item,
+    Member,
+}
+
+#[derive(Default)]
+struct Parser {
+    version: Option<(String, String)>,
+    domains: Vec<Domain>,
+    /// Every file read, in order.
+    files: Vec<PathBuf>,
+    /// The files being parsed, to report include cycles.
+    stack: Vec<PathBuf>,
+    description: Vec<String>,
+    item: Option<Item>,
+    members: Option<Members>,
+    literals: Option<Literals>,
+}
+
+impl Parser {
+    fn parse_file(&mut self, path: &Path) -> Result<(), String> {
+        if self.stack.iter().any(|file| file == path) {
+            return Err(format!("{}: included recursively", path.display()));
+        }
+
+        let source = fs::read_to_string(path)
+            .map_err(|err| format!("could not read {}: {}", path.display(), err))?;
+
+        self.files.push(path.to_path_buf());
+        self.stack.push(path.to_path_buf());
+
+        for (index, line) in source.lines().enumerate() {
+            self.parse_line(path, line)
+                .map_err(|err| format!("{}:{}: {}", path.display(), index + 1, err))?;
+        }
+
+        self.stack.pop();
+        Ok(())
+    }
+
+    fn parse_line(&mut self, path: &Path, line: &str) -> Result<(), String> {
+        let trimmed = line.trim();
+
+        if let Some(comment) = trimmed.strip_prefix('#') {
+            self.description
+                .push(comment.strip_prefix(' ').unwrap_or(comment).to_string());
+            return Ok(());
+        }
+
+        // comments only describe the item right below them
+        let description = if self.description.is_empty() {
+            None
+        } else {
+            Some(self.description.join("\n"))
+        };
+        self.description.clear();
+
+        if trimmed.is_empty() {
+            return Ok(());
+        }
+
+        let indent = line.len() - line.trim_start_matches(' ').len();
+        let words: Vec<&str> = trimmed.split_whitespace().collect();
+
+        match (indent, words.as_slice()) {
+            (0, ["version"]) => Ok(()),
+            (0, ["include", file]) => {
+                let file = path.parent().unwrap_or_else(|| Path::new("")).join(file);
+                self.parse_file(&file)
+            }
+            (0, _) => match modifiers(&words) {
+                (experimental, _, ["domain", name]) => {
+                    self.domains.push(Domain {
+                        domain: name.to_string(),
+                        experimental,
+                        dependencies: None,
+                        types: None,
+                        commands: Vec::new(),
+                        events: None,
+                        description,
+                    });
+                    self.item = None;
+                    self.members = None;
+                    self.literals = None;
+                    Ok(())
+                }
+                _ => Err(format!("unexpected `{}`", trimmed)),
+            },
+            (2, ["major", major]) => {
+                self.version.get_or_insert_with(Default::default).0 = major.to_string();
+                Ok(())
+            }
+            (2, ["minor", minor]) => {
+                self.version.get_or_insert_with(Default::default).1 = minor.to_string();
+                Ok(())
+            }
+            (2, ["depends", "on", dependency]) => {
+                self.domain()?
+                    .dependencies
+                    .get_or_insert_with(Vec::new)
+                    .push(dependency.to_string());
+                Ok(())
+            }
+            (2, _) => self.parse_item(&words, description),
+            (4, ["parameters"]) => self.start_members(Members::Parameters),
+            (4, ["returns"]) => self.start_members(Members::Returns),
+            (4, ["properties"]) => self.start_members(Members::Properties),
+            (4, ["enum"]) => {
+                match self.item {
+                    Some(Item::Type) => self.type_element()?.type_enum = Some(Vec::new()),
+                    _ => return Err(String::from("`enum` outside a type")),
+                }
+                self.literals = Some(Literals::Item);
+                Ok(())
+            }
+            (4, ["redirect", domain]) => match self.item {
+                Some(Item::Command) => {
+                    self.command()?.redirect = Some(domain.to_string());
+                    Ok(())
+                }
+                _ => Err(String::from("`redirect` outside a command")),
+            },
+            (6, [literal]) => self.add_literal(Literals::Item, literal),
+            (6, _) => self.parse_member(&words, description),
+            (8, [literal]) => self.add_literal(Literals::Member, literal),
+            _ => Err(format!("unexpected `{}`", trimmed)),
+        }
+    }
+
+    /// `type`, `command` and `event` lines.
+    fn parse_item(&mut self, words: &[&str], description: Option<String>) -> Result<(), String> {
+        let (experimental, deprecated, words) = modifiers(words);
+        self.members = None;
+        self.literals = None;
+
+        match words {
+            ["type", id, "extends", rest @ ..] => {
+                let (items, type_name) = match rest {
+                    ["array", "of", type_name] => (Some(items(type_name)), "array"),
+                    [type_name] => (None, *type_name),
+                    _ => return Err(format!("unexpected `extends {}`", rest.join(" "))),
+                };
+                let description = described(type_name, description);
+                let type_type = primitive(type_name).ok_or_else(|| {
+                    format!(
+                        "type `{}` must extend a primitive type, not `{}`",
+                        id, type_name
+                    )
+                })?;
+
+                self.domain()?
+                    .types
+                    .get_or_insert_with(Vec::new)
+                    .push(TypeElement {
+                        id: id.to_string(),
+                        description,
+                        type_type,
+                        type_enum: None,
+                        properties: None,
+                        experimental,
+                        items,
+                        deprecated,
+                    });
+                self.item = Some(Item::Type);
+            }
+            ["command", name] => {
+                self.domain()?.commands.push(Command {
+                    name: name.to_string(),
+                    description,
+                    experimental,
+                    parameters: None,
+                    returns: None,
+                    deprecated,
+                    redirect: None,
+                });
+                self.item = Some(Item::Command);
+            }
+            ["event", name] => {
+                self.domain()?
+                    .events
+                    .get_or_insert_with(Vec::new)
+                    .push(Event {
+                        name: name.to_string(),
+                        description,
+                        parameters: None,
+                        experimental,
+                        deprecated,
+                    });
+                self.item = Some(Item::Event);
+            }
+            _ => return Err(format!("unexpected `{}`", words.join(" "))),
+        }
+
+        Ok(())
+    }
+
+    /// Properties, parameters and return values, e.g. `optional array of string urls`.
+    fn parse_member(&mut self, words: &[&str], description: Option<String>) -> Result<(), String> {
+        let (experimental, deprecated, words) = modifiers(words);
+        let (optional, words) = match words {
+            ["optional", rest @ ..] => (Some(true), rest),
+            _ => (None, words),
+        };
+        let (array, type_name, name) = match words {
+            ["array", "of", type_name, name] => (true, *type_name, *name),
+            [type_name, name] => (false, *type_name, *name),
+            _ => return Err(format!("unexpected `{}`", words.join(" "))),
+        };
+
+        let description = if array {
+            description
+        } else {
+            described(type_name, description)
+        };
+        let mut parameter = Parameter {
+            name: name.to_string(),
+            description,
+            optional,
+            parameter_ref: None,
+            parameter_type: None,
+            items: None,
+            parameter_enum: None,
+            experimental,
+            deprecated,
+        };
+        if array {
+            parameter.parameter_type = Some(TypeEnum::Array);
+            parameter.items = Some(items(type_name));
+        } else if let Some(primitive) = primitive(type_name) {
+            parameter.parameter_type = Some(primitive);
+        } else {
+            parameter.parameter_ref = Some(type_name.to_string());
+        }
+        if type_name == "enum" {
+            parameter.parameter_enum = Some(Vec::new());
+            self.literals = Some(Literals::Member);
+        } else {
+            self.literals = None;
+        }
+
+        self.members()?.push(parameter);
+        Ok(())
+    }
+
+    fn start_members(&mut self, members: Members) -> Result<(), String> {
+        let list = match (self.item, members) {
+            (Some(Item::Type), Members::Properties) => &mut self.type_element()?.properties,
+            (Some(Item::Command), Members::Parameters) => &mut self.command()?.parameters,
+            (Some(Item::Command), Members::Returns) => &mut self.command()?.returns,
+            (Some(Item::Event), Members::Parameters) => &mut self.event()?.parameters,
+            _ => return Err(String::from("members outside a type, command or event")),
+        };
+        list.get_or_insert_with(Vec::new);
+
+        self.members = Some(members);
+        self.literals = None;
+        Ok(())
+    }
+
+    fn add_literal(&mut self, literals: Literals, literal: &str) -> Result<(), String> {
+        let list = match (self.literals, literals) {
+            (Some(Literals::Item), Literals::Item) => self.type_element()?.type_enum.as_mut(),
+            (Some(Literals::Member), _) => {
+                self.members()?.last_mut().unwrap().parameter_enum.as_mut()
+            }
+            _ => None,
+        };
+
+        list.ok_or_else(|| format!("unexpected `{}`", literal))?
+            .push(literal.to_string());
+        Ok(())
+    }
+
+    fn domain(&mut self) -> Result<&mut Domain, String> {
+        self.domains
+            .last_mut()
+            .ok_or_else(|| String::from("expected a `domain` first"))
+    }
+
+    fn type_element(&mut self) -> Result<&mut TypeElement, String> {
+        self.domain()?
+            .types
+            .as_mut()
+            .and_then(|types| types.last_mut())
+            .ok_or_else(|| String::from("expected a `type` first"))
+    }
+
+    fn command(&mut self) -> Result<&mut Command, String> {
+        self.domain()?
+            .commands
+            .last_mut()
+            .ok_or_else(|| String::from("expected a `command` first"))
+    }
+
+    fn event(&mut self) -> Result<&mut Event, String> {
+        self.domain()?
+            .events
+            .as_mut()
+            .and_then(|events| events.last_mut())
+            .ok_or_else(|| String::from("expected an `event` first"))
+    }
+
+    fn members(&mut self) -> Result<&mut Vec<Parameter>, String> {
+        let members = match (self.item, self.members) {
+            (Some(Item::Type), Some(Members::Properties)) => &mut self.type_element()?.properties,
+            (Some(Item::Command), Some(Members::Parameters)) => &mut self.command()?.parameters,
+            (Some(Item::Command), Some(Members::Returns)) => &mut self.command()?.returns,
+            (Some(Item::Event), Some(Members::Parameters)) => &mut self.event()?.parameters,
+            _ => {
+                return Err(String::from(
+                    "expected `parameters`, `returns` or `properties` first",
+                ))
+            }
+        };
+
+        Ok(members.get_or_insert_with(Vec::new))
+    }
+}
+
+/// Splits the leading `experimental` and `deprecated` off `words`.
+fn modifiers<'a, 'b>(mut words: &'a [&'b str]) -> (Option<bool>, Option<bool>, &'a [&'b str]) {
+    let mut experimental = None;
+    let mut deprecated = None;
+
+    loop {
+        match words.split_first() {
+            Some((&"experimental", rest)) => {
+                experimental = Some(true);
+                words = rest;
+            }
+            Some((&"deprecated", rest)) => {
+                deprecated = Some(true);
+                words = rest;
+            }
+            _ => return (experimental, deprecated, words),
+        }
+    }
+}
+
+/// The JSON type of a PDL type name, `None` for references to other types.
+fn primitive(type_name: &str) -> Option<TypeEnum> {
+    match type_name {
+        "any" => Some(TypeEnum::Any),
+        "array" => Some(TypeEnum::Array),
+        "boolean" => Some(TypeEnum::Boolean),
+        "integer" => Some(TypeEnum::Integer),
+        "number" => Some(TypeEnum::Number),
+        "object" => Some(TypeEnum::Object),
+        // binary is sent base64 encoded
+        "string" | "enum" | "binary" => Some(TypeEnum::String),
+        _ => None,
+    }
+}
+
+/// `description` with the note pdl.py adds to binary values, which mark them as
+/// binary for the `cbor` feature.
+fn described(type_name: &str, description: Option<String>) -> Option<String> {
+    match description {
+        Some(description) if type_name == "binary" => {
+            Some(format!("{} ({})", description, BINARY_NOTE))
+        }
+        description => description,
+    }
+}
+
+fn items(type_name: &str) -> Items {
+    match primitive(type_name) {
+        Some(items_type) => Items {
+            items_type: Some(items_type),
+            items_ref: None,
+        },
+        None => Items {
+            items_type: None,
+            items_ref: Some(type_name.to_string()),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::env;
+
+    const PAGE: &str = "\
+version
+  major 1
+  minor 3
+
+# Actions and events related to the inspected page.
+experimental domain Page
+  depends on Network
+
+  # Unique frame identifier.
+  type FrameId extends string
+
+  type Frame extends object
+    properties
+      FrameId id
+      optional array of string urls
+      experimental optional enum crossOriginIsolation
+        Isolated
+        NotIsolated
+
+  deprecated command deleteCookie
+    parameters
+      string cookieName
+    redirect Network
+
+  event frameDetached
+    parameters
+      Frame frame
+
+include network.pdl
+";
+
+    const NETWORK: &str = "\
+domain Network
+  type Headers extends object
+
+  command deleteCookie
+";
+
+    fn write(dir: &Path, file_name: &str, contents: &str) -> PathBuf {
+        let path = dir.join(file_name);
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn parses_pdl() {
+        let dir = env::temp_dir().join("auto_generate_cdp_pdl");
+        fs::create_dir_all(&dir).unwrap();
+        write(&dir, "network.pdl", NETWORK);
+        let page = write(&dir, "page.pdl", PAGE);
+
+        let pdl = parse_file(&page).unwrap();
+        let domains = &pdl.protocol.domains;
+
+        assert_eq!(pdl.files, vec![page, dir.join("network.pdl")]);
+        assert_eq!(pdl.protocol.version.minor, "3");
+        assert_eq!(domains.len(), 2);
+
+        let page = &domains[0];
+        assert_eq!(page.experimental, Some(true));
+        assert_eq!(page.dependencies, Some(vec![String::from("Network")]));
+
+        let types = page.types.as_ref().unwrap();
+        assert_eq!(
+            types[0].description.as_deref(),
+            Some("Unique frame identifier.")
+        );
+
+        let properties = types[1].properties.as_ref().unwrap();
+        assert_eq!(properties[0].parameter_ref.as_deref(), Some("FrameId"));
+        assert_eq!(properties[1].optional, Some(true));
+        assert!(matches!(
+            properties[1].items.as_ref().unwrap().items_type,
+            Some(TypeEnum::String)
+        ));
+        assert_eq!(properties[2].experimental, Some(true));
+        assert_eq!(
+            properties[2].parameter_enum,
+            Some(vec![String::from("Isolated"), String::from("NotIsolated")])
+        );
+
+        assert_eq!(page.commands[0].deprecated, Some(true));
+        assert_eq!(page.commands[0].redirect.as_deref(), Some("Network"));
+        assert_eq!(page.events.as_ref().unwrap()[0].name, "frameDetached");
+        assert_eq!(domains[1].commands[0].name, "deleteCookie");
+    }
+
+    #[test]
+    fn notes_binary_like_json() {
+        let dir = env::temp_dir().join("auto_generate_cdp_pdl_binary");
+        fs::create_dir_all(&dir).unwrap();
+        let page = write(
+            &dir,
+            "page.pdl",
+            "\
+version
+  major 1
+  minor 3
+
+domain Page
+  command captureScreenshot
+    returns
+      # Base64-encoded image data.
+      binary data
+",
+        );
+        let json = Protocol::from_json(
+            r#"{
+                "version": { "major": "1", "minor": "3" },
+                "domains": [{
+                    "domain": "Page",
+                    "commands": [{
+                        "name": "captureScreenshot",
+                        "returns": [{
+                            "name": "data",
+                            "description": "Base64-encoded image data. (Encoded as a base64 string when passed over JSON)",
+                            "type": "string"
+                        }]
+                    }]
+                }]
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(parse_file(&page).unwrap().protocol, json);
+    }
+
+    #[test]
+    fn reports_line_numbers() {
+        let dir = env::temp_dir().join("auto_generate_cdp_pdl_error");
+        fs::create_dir_all(&dir).unwrap();
+        let page = write(
+            &dir,
+            "page.pdl",
+            &PAGE.replace(
+                "  type FrameId extends string",
+                "  type FrameId extend string",
+            ),
+        );
+
+        let err = parse_file(&page).err().unwrap();
+        assert_eq!(
+            err,
+            format!(
+                "{}:10: unexpected `type FrameId extend string`",
+                page.display()
+            )
+        );
+    }
+}
@@ -0,0 +1,324 @@
+//! Helpers on the `Runtime` types holding JavaScript values, which are JSON values
+//! unless JSON can't represent them, like `NaN` or bigints.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::lifetimes::Lifetimes;
+use crate::symbols::SymbolTable;
+use crate::types::{Domain, TypeElement};
+
+/// The helpers of the `Runtime` domain `dom`, nothing for other domains or if its
+/// `RemoteObject` and `CallArgument` aren't the ones the helpers are written for,
+/// or are overridden along with the types of their fields.
+pub fn helpers(dom: &Domain, symbols: &SymbolTable, lifetimes: &Lifetimes) -> TokenStream {
+    if dom.domain != "Runtime" {
+        return quote! {};
+    }
+
+    let generated = |ids: &[&str]| ids.iter().all(|id| !symbols.is_overridden("Runtime", id));
+    let remote_object = dom
+        .find_type("RemoteObject")
+        .filter(|element| has_properties(element, &["value", "unserializableValue"]))
+        .filter(|_| generated(&["RemoteObject", "UnserializableValue"]));
+    let call_argument = dom
+        .find_type("CallArgument")
+        .filter(|element| {
+            let properties = element.properties.as_deref().unwrap_or_default();
+            properties.len() == 3
+                && has_properties(element, &["value", "unserializableValue", "objectId"])
+        })
+        .filter(|_| {
+            generated(&[
+                "CallArgument",
+                "RemoteObject",
+                "RemoteObjectId",
+                "UnserializableValue",
+            ])
+        });
+
+    let to_value = remote_object.map(|_| {
+        let generics = lifetimes.generics(lifetimes.of_ref("Runtime", "RemoteObject"));
+        let value = if lifetimes.borrowed() {
+            quote! { serde_json::from_str(value.get()) }
+        } else {
+            quote! { T::deserialize(value) }
+        };
+
+        quote! {
+            impl #generics RemoteObject #generics {
+                /// The value of the object as `T`. `NaN`, `Infinity`, `-Infinity` and
+                /// `-0` are read as floats and bigints like `1n` as integers, and
+                /// `undefined`, `null` and the objects only held by reference as `()`.
+                pub fn to_value<'de, T: Deserialize<'de>>(&'de self) -> serde_json::Result<T> {
+                    if let Some(unserializable) = self.unserializable_value.as_deref() {
+                        return remote_value::deserialize(unserializable);
+                    }
+                    match &self.value {
+                        Some(value) => #value,
+                        None => T::deserialize(serde::de::value::UnitDeserializer::new()),
+                    }
+                }
+            }
+        }
+    });
+
+    // the arguments of borrowed types can't hold the JSON they are built from
+    let call_argument = call_argument.filter(|_| !lifetimes.borrowed()).map(|_| {
+        quote! {
+            impl CallArgument {
+                /// An argument with `value`, sending the numbers JSON can't represent,
+                /// like `NaN` or integers out of the range of `i64` and `u64`, as unserializable values.
+                /// Such numbers in a sequence or map are an error, as they'd be sent as `null`.
+                pub fn from_value<T: Serialize + ?Sized>(value: &T) -> serde_json::Result<Self> {
+                    Ok(match remote_value::unserializable(value)? {
+                        Some(unserializable) => CallArgument {
+                            value: None,
+                            unserializable_value: Some(unserializable),
+                            object_id: None,
+                        },
+                        None => CallArgument {
+                            value: Some(serde_json::to_value(value)?),
+                            unserializable_value: None,
+                            object_id: None,
+                        },
+                    })
+                }
+
+                /// An argument passing `object` back, by reference if it has an id.
+                pub fn from_remote_object(object: &RemoteObject) -> Self {
+                    match &object.object_id {
+                        Some(object_id) => CallArgument::from_object_id(object_id.clone()),
+                        None => CallArgument {
+                            value: object.value.clone(),
+                            unserializable_value: object.unserializable_value.clone(),
+                            object_id: None,
+                        },
+                    }
+                }
+
+                /// An argument referring to the remote object `object_id`.
+                pub fn from_object_id(object_id: RemoteObjectId) -> Self {
+                    CallArgument {
+                        value: None,
+                        unserializable_value: None,
+                        object_id: Some(object_id),
+                    }
+                }
+
+                /// The `undefined` argument.
+                pub fn undefined() -> Self {
+                    CallArgument {
+                        value: None,
+                        unserializable_value: None,
+                        object_id: None,
+                    }
+                }
+            }
+        }
+    });
+
+    if to_value.is_none() && call_argument.is_none() {
+        return quote! {};
+    }
+
+    quote! {
+        #to_value
+
+        #call_argument
+
+        /// Conversions of the unserializable values of remote objects.
+        mod remote_value {
+            use serde::de::value::{F64Deserializer, I128Deserializer, I64Deserializer, U128Deserializer, U64Deserializer};
+            use serde::{Deserialize, Serialize};
+
+            pub fn deserialize<'de, T: Deserialize<'de>>(value: &str) -> serde_json::Result<T> {
+                let float = match value {
+                    "NaN" => Some(f64::NAN),
+                    "Infinity" => Some(f64::INFINITY),
+                    "-Infinity" => Some(f64::NEG_INFINITY),
+                    "-0" => Some(-0.0),
+                    _ => None,
+                };
+                if let Some(float) = float {
+                    return T::deserialize(F64Deserializer::new(float));
+                }
+
+                let bigint = value.strip_suffix('n').ok_or_else(|| {
+                    serde::de::Error::custom(format_args!("unknown unserializable value `{}`", value))
+                })?;
+                if let Ok(int) = bigint.parse::<i64>() {
+                    T::deserialize(I64Deserializer::new(int))
+                } else if let Ok(int) = bigint.parse::<u64>() {
+                    T::deserialize(U64Deserializer::new(int))
+                } else if let Ok(int) = bigint.parse::<i128>() {
+                    T::deserialize(I128Deserializer::new(int))
+                } else if let Ok(int) = bigint.parse::<u128>() {
+                    T::deserialize(U128Deserializer::new(int))
+                } else {
+                    Err(serde::de::Error::custom(format_args!("bigint `{}` out of range", value)))
+                }
+            }
+
+            /// The unserializable value of `value`, if it is a number JSON can't represent,
+            /// or an error if such a number is in a sequence or map.
+            pub fn unserializable<T: Serialize + ?Sized>(value: &T) -> serde_json::Result<Option<String>> {
+                value.serialize(Unserializable)
+            }
+
+            /// Checks that an element of a sequence or map is representable.
+            fn representable<T: Serialize + ?Sized>(value: &T) -> serde_json::Result<()> {
+                match unserializable(value)? {
+                    Some(_) => Err(serde::ser::Error::custom("unrepresentable number in compound value")),
+                    None => Ok(()),
+                }
+            }
+
+            struct Unserializable;
+
+            /// A sequence or map, whose elements are checked as they are serialized.
+            struct Compound;
+
+            macro_rules! elements {
+                ($($compound:ident { $($method:ident($($key:ty)?);)* })*) => {
+                    $(
+                        impl serde::ser::$compound for Compound {
+                            type Ok = Option<String>;
+                            type Error = serde_json::Error;
+
+                            $(
+                                fn $method<T: Serialize + ?Sized>(&mut self, $(_: $key,)? value: &T) -> serde_json::Result<()> {
+                                    representable(value)
+                                }
+                            )*
+
+                            fn end(self) -> serde_json::Result<Option<String>> {
+                                Ok(None)
+                            }
+                        }
+                    )*
+                };
+            }
+
+            elements! {
+                SerializeSeq { serialize_element(); }
+                SerializeTuple { serialize_element(); }
+                SerializeTupleStruct { serialize_field(); }
+                SerializeTupleVariant { serialize_field(); }
+                SerializeMap { serialize_key(); serialize_value(); }
+                SerializeStruct { serialize_field(&'static str); }
+                SerializeStructVariant { serialize_field(&'static str); }
+            }
+
+            macro_rules! representable {
+                ($($method:ident($($value:ty),*);)*) => {
+                    $(
+                        fn $method(self, $(_: $value),*) -> serde_json::Result<Option<String>> {
+                            Ok(None)
+                        }
+                    )*
+                };
+            }
+
+            macro_rules! compound {
+                ($($method:ident($($value:ty),*) -> $compound:ident;)*) => {
+                    $(
+                        fn $method(self, $(_: $value),*) -> serde_json::Result<Self::$compound> {
+                            Ok(Compound)
+                        }
+                    )*
+                };
+            }
+
+            impl serde::Serializer for Unserializable {
+                type Ok = Option<String>;
+                type Error = serde_json::Error;
+                type SerializeSeq = Compound;
+                type SerializeTuple = Compound;
+                type SerializeTupleStruct = Compound;
+                type SerializeTupleVariant = Compound;
+                type SerializeMap = Compound;
+                type SerializeStruct = Compound;
+                type SerializeStructVariant = Compound;
+
+                representable! {
+                    serialize_bool(bool);
+                    serialize_i8(i8);
+                    serialize_i16(i16);
+                    serialize_i32(i32);
+                    serialize_i64(i64);
+                    serialize_u8(u8);
+                    serialize_u16(u16);
+                    serialize_u32(u32);
+                    serialize_u64(u64);
+                    serialize_char(char);
+                    serialize_str(&str);
+                    serialize_bytes(&[u8]);
+                    serialize_none();
+                    serialize_unit();
+                    serialize_unit_struct(&'static str);
+                    serialize_unit_variant(&'static str, u32, &'static str);
+                }
+
+                compound! {
+                    serialize_seq(Option<usize>) -> SerializeSeq;
+                    serialize_tuple(usize) -> SerializeTuple;
+                    serialize_tuple_struct(&'static str, usize) -> SerializeTupleStruct;
+                    serialize_tuple_variant(&'static str, u32, &'static str, usize) -> SerializeTupleVariant;
+                    serialize_map(Option<usize>) -> SerializeMap;
+                    serialize_struct(&'static str, usize) -> SerializeStruct;
+                    serialize_struct_variant(&'static str, u32, &'static str, usize) -> SerializeStructVariant;
+                }
+
+                fn serialize_f32(self, value: f32) -> serde_json::Result<Option<String>> {
+                    self.serialize_f64(f64::from(value))
+                }
+
+                fn serialize_f64(self, value: f64) -> serde_json::Result<Option<String>> {
+                    let unserializable = if value.is_nan() {
+                        "NaN"
+                    } else if value == f64::INFINITY {
+                        "Infinity"
+                    } else if value == f64::NEG_INFINITY {
+                        "-Infinity"
+                    } else if value == 0.0 && value.is_sign_negative() {
+                        "-0"
+                    } else {
+                        return Ok(None);
+                    };
+                    Ok(Some(unserializable.to_string()))
+                }
+
+                fn serialize_i128(self, value: i128) -> serde_json::Result<Option<String>> {
+                    let representable = value >= i128::from(i64::MIN) && value <= i128::from(u64::MAX);
+                    Ok(Some(format!("{}n", value)).filter(|_| !representable))
+                }
+
+                fn serialize_u128(self, value: u128) -> serde_json::Result<Option<String>> {
+                    let representable = value <= u128::from(u64::MAX);
+                    Ok(Some(format!("{}n", value)).filter(|_| !representable))
+                }
+
+                fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> serde_json::Result<Option<String>> {
+                    value.serialize(self)
+                }
+
+                fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _: &'static str, value: &T) -> serde_json::Result<Option<String>> {
+                    value.serialize(self)
+                }
+
+                fn serialize_newtype_variant<T: Serialize + ?Sized>(self, _: &'static str, _: u32, _: &'static str, value: &T) -> serde_json::Result<Option<String>> {
+                    // sent as a map of the variant to `value`
+                    representable(value).map(|()| None)
+                }
+            }
+        }
+    }
+}
+
+fn has_properties(element: &TypeElement, names: &[&str]) -> bool {
+    let properties = element.properties.as_deref().unwrap_or_default();
+    names
+        .iter()
+        .all(|name| properties.iter().any(|property| property.name == *name))
+}
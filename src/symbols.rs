@@ -0,0 +1,760 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use convert_case::{Case, Casing};
+
+use crate::compile::StringUtils;
+use crate::ident::{field_name, type_ident, type_name};
+use crate::types::{split_ref, Items, Parameter, Protocol, TypeElement, TypeEnum};
+
+/// Where in a generated domain module a reference is emitted from.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Scope {
+    /// The domain module itself, e.g. `cdp::Page`.
+    Domain,
+    /// The `events` module of the domain, e.g. `cdp::Page::events`.
+    Events,
+}
+
+/// A protocol type a `$ref` resolved to.
+pub struct Symbol<'a> {
+    pub domain: &'a str,
+    pub element: &'a TypeElement,
+}
+
+/// What declares the fields of an inline enum. Only the fields of one kind share
+/// enums, so that e.g. an event's field is never typed after a command.
+#[derive(Clone, Copy, PartialEq)]
+enum OwnerKind {
+    Type,
+    Command,
+    Event,
+}
+
+/// An enum of strings declared by a field instead of as a type. Fields of a domain
+/// with the same name and values, declared by the same kind of item, share one.
+pub struct InlineEnum<'a> {
+    /// Rust name of the enum, the name of the type, command or event declaring it
+    /// followed by the name of the field. Of the names of the fields sharing it, it
+    /// takes the smallest, which doesn't change when the protocol is reordered.
+    pub name: String,
+    /// The names of the other fields sharing it, generated as aliases of it so that
+    /// none of them goes away when another field comes to share it.
+    pub aliases: Vec<String>,
+    /// Protocol name of the type, command or event it is named after, whose derives
+    /// and attributes it has.
+    pub owner: &'a str,
+    /// Protocol names of every type, command or event with a field sharing it.
+    owners: Vec<&'a str>,
+    kind: OwnerKind,
+    pub field: &'a str,
+    pub values: &'a [String],
+}
+
+/// Every type and command of the protocols being generated, built before any code
+/// is generated so that `$ref`s can be resolved across domains and files.
+pub struct SymbolTable<'a> {
+    types: HashMap<String, Symbol<'a>>,
+    commands: HashSet<String>,
+    dependencies: HashMap<&'a str, BTreeSet<&'a str>>,
+    /// Paths of the Rust types replacing protocol types, keyed by `Domain.Type`.
+    overrides: HashMap<String, TokenStream>,
+    /// Every type as `Domain.Type`, in the order they are defined.
+    order: Vec<String>,
+    /// The fields boxed to break the cycles of types holding each other, as
+    /// `Domain.Type` and field name.
+    boxed: HashSet<(String, String)>,
+    /// The inline enums of every domain, in the order they are declared.
+    inline_enums: HashMap<&'a str, Vec<InlineEnum<'a>>>,
+}
+
+impl<'a> SymbolTable<'a> {
+    /// Builds the table for `protocols`, panicking with every `$ref` that does not
+    /// resolve to a type.
+    pub fn new(protocols: &'a [Protocol]) -> Self {
+        let mut symbols = SymbolTable {
+            types: HashMap::new(),
+            commands: HashSet::new(),
+            dependencies: HashMap::new(),
+            overrides: HashMap::new(),
+            order: Vec::new(),
+            boxed: HashSet::new(),
+            inline_enums: HashMap::new(),
+        };
+
+        for dom in protocols.iter().flat_map(|protocol| &protocol.domains) {
+            for element in dom.types.as_deref().unwrap_or_default() {
+                let key = format!("{}.{}", dom.domain, element.id);
+                symbols.order.push(key.clone());
+                symbols.types.insert(
+                    key,
+                    Symbol {
+                        domain: &dom.domain,
+                        element,
+                    },
+                );
+            }
+            for command in &dom.commands {
+                symbols
+                    .commands
+                    .insert(format!("{}.{}", dom.domain, command.name));
+            }
+        }
+
+        let mut unknown = Vec::new();
+
+        for dom in protocols.iter().flat_map(|protocol| &protocol.domains) {
+            let mut references = Vec::new();
+
+            for element in dom.types.as_deref().unwrap_or_default() {
+                let context = format!("{}.{}", dom.domain, element.id);
+                collect_items(&context, element.items.as_ref(), &mut references);
+                collect_parameters(&context, element.properties.as_deref(), &mut references);
+            }
+            for command in &dom.commands {
+                let context = format!("{}.{}", dom.domain, command.name);
+                collect_parameters(&context, command.parameters.as_deref(), &mut references);
+                collect_parameters(&context, command.returns.as_deref(), &mut references);
+            }
+            for event in dom.events.as_deref().unwrap_or_default() {
+                let context = format!("{}.{}", dom.domain, event.name);
+                collect_parameters(&context, event.parameters.as_deref(), &mut references);
+            }
+
+            let mut dependencies = BTreeSet::new();
+
+            for (context, reference) in references {
+                match symbols.resolve(&dom.domain, reference) {
+                    Some(symbol) if symbol.domain != dom.domain => {
+                        dependencies.insert(symbol.domain);
+                    }
+                    Some(_) => {}
+                    None => unknown.push(format!(
+                        "`{}` refers to unknown type `{}`",
+                        context, reference
+                    )),
+                }
+            }
+
+            symbols.dependencies.insert(&dom.domain, dependencies);
+        }
+
+        if !unknown.is_empty() {
+            panic!("unresolved protocol references:\n{}", unknown.join("\n"));
+        }
+
+        symbols.find_cycles();
+        symbols.name_inline_enums(protocols);
+        symbols
+    }
+
+    /// Names the enums declared by fields, panicking with every name given to two
+    /// of them or already taken by a type or command of the domain.
+    fn name_inline_enums(&mut self, protocols: &'a [Protocol]) {
+        let mut collisions = Vec::new();
+
+        for dom in protocols.iter().flat_map(|protocol| &protocol.domains) {
+            let mut owners: Vec<(OwnerKind, &str, &[Parameter])> = Vec::new();
+            let mut taken: HashMap<String, String> = HashMap::new();
+
+            for element in dom.types.as_deref().unwrap_or_default() {
+                owners.push((
+                    OwnerKind::Type,
+                    &element.id,
+                    element.properties.as_deref().unwrap_or_default(),
+                ));
+                taken.insert(
+                    type_name(&element.id),
+                    format!("the type `{}.{}`", dom.domain, element.id),
+                );
+            }
+            for command in &dom.commands {
+                owners.push((
+                    OwnerKind::Command,
+                    &command.name,
+                    command.parameters.as_deref().unwrap_or_default(),
+                ));
+                owners.push((
+                    OwnerKind::Command,
+                    &command.name,
+                    command.returns.as_deref().unwrap_or_default(),
+                ));
+                let name = type_name(&command.name.clone().first_uppercased());
+                let context = format!("the command `{}.{}`", dom.domain, command.name);
+                taken.insert(name.clone() + "ReturnObject", context.clone());
+                taken.insert(name, context);
+            }
+            for event in dom.events.as_deref().unwrap_or_default() {
+                owners.push((
+                    OwnerKind::Event,
+                    &event.name,
+                    event.parameters.as_deref().unwrap_or_default(),
+                ));
+            }
+
+            let mut inline_enums: Vec<InlineEnum> = Vec::new();
+
+            for (kind, owner, fields) in owners {
+                for field in fields {
+                    let values = match &field.parameter_enum {
+                        Some(values) => values.as_slice(),
+                        None => continue,
+                    };
+                    let name = type_name(&format!(
+                        "{}{}",
+                        owner.to_string().first_uppercased(),
+                        field_name(&field.name).to_case(Case::Pascal)
+                    ));
+
+                    let shared = inline_enums.iter_mut().find(|shared| {
+                        shared.kind == kind && shared.field == field.name && shared.values == values
+                    });
+                    match shared {
+                        Some(shared) => {
+                            shared.owners.push(owner);
+                            if name < shared.name {
+                                let alias = std::mem::replace(&mut shared.name, name);
+                                shared.aliases.push(alias);
+                                shared.owner = owner;
+                            } else if name != shared.name && !shared.aliases.contains(&name) {
+                                shared.aliases.push(name);
+                            }
+                        }
+                        None => inline_enums.push(InlineEnum {
+                            name,
+                            aliases: Vec::new(),
+                            owner,
+                            owners: vec![owner],
+                            kind,
+                            field: &field.name,
+                            values,
+                        }),
+                    }
+                }
+            }
+
+            inline_enums.sort_by(|a, b| a.name.cmp(&b.name));
+            for inline_enum in &mut inline_enums {
+                inline_enum.aliases.sort();
+                let context = format!(
+                    "the field `{}.{}.{}`",
+                    dom.domain, inline_enum.owner, inline_enum.field
+                );
+                for name in std::iter::once(&inline_enum.name).chain(&inline_enum.aliases) {
+                    match taken.get(name) {
+                        Some(previous) => collisions.push(format!(
+                            "{} and {} are both named `{}`",
+                            previous, context, name
+                        )),
+                        None => {
+                            taken.insert(name.clone(), context.clone());
+                        }
+                    }
+                }
+            }
+
+            self.inline_enums
+                .entry(&dom.domain)
+                .or_default()
+                .extend(inline_enums);
+        }
+
+        if !collisions.is_empty() {
+            panic!("inline enum names collide:\n{}", collisions.join("\n"));
+        }
+    }
+
+    /// Replaces the protocol types `overrides` maps from `Domain.Type` with the Rust
+    /// types at the paths they map to, panicking with every one that isn't a type.
+    pub fn with_overrides(mut self, overrides: &BTreeMap<String, String>) -> Self {
+        let mut unknown = Vec::new();
+
+        for (id, path) in overrides {
+            if !id.contains('.') || !self.types.contains_key(id) {
+                unknown.push(format!("`{}` overrides unknown type `{}`", path, id));
+                continue;
+            }
+            let path = path
+                .parse()
+                .unwrap_or_else(|_| panic!("invalid type path `{}` for `{}`", path, id));
+            self.overrides.insert(id.clone(), path);
+        }
+
+        if !unknown.is_empty() {
+            panic!("unresolved type overrides:\n{}", unknown.join("\n"));
+        }
+
+        // the types replacing protocol types hold none of them
+        self.find_cycles();
+        self
+    }
+
+    /// Boxes the fields that close a cycle of types holding each other directly or
+    /// through optional fields, the back edges of a depth-first search in
+    /// definition order. Lists and maps hold their items on the heap already.
+    fn find_cycles(&mut self) {
+        #[derive(Clone, Copy, PartialEq)]
+        enum State {
+            Visiting,
+            Visited,
+        }
+
+        let mut states: HashMap<&str, State> = HashMap::new();
+        let mut boxed = HashSet::new();
+
+        for root in &self.order {
+            if states.contains_key(root.as_str()) {
+                continue;
+            }
+            states.insert(root, State::Visiting);
+            let mut stack = vec![(root.as_str(), self.held(root))];
+
+            while let Some((key, fields)) = stack.last_mut() {
+                let key = *key;
+                match fields.next() {
+                    Some((field, held)) => match states.get(held) {
+                        Some(State::Visiting) => {
+                            boxed.insert((key.to_string(), field.to_string()));
+                        }
+                        Some(State::Visited) => {}
+                        None => {
+                            states.insert(held, State::Visiting);
+                            stack.push((held, self.held(held)));
+                        }
+                    },
+                    None => {
+                        states.insert(key, State::Visited);
+                        stack.pop();
+                    }
+                }
+            }
+        }
+
+        self.boxed = boxed;
+    }
+
+    /// The fields of the type `key` holding another generated type by value, with
+    /// that type's `Domain.Type`.
+    fn held<'b>(&'b self, key: &str) -> impl Iterator<Item = (&'b str, &'b str)> + 'b {
+        let symbol = &self.types[key];
+        let properties = match symbol.element.type_type {
+            TypeEnum::Object if !self.overrides.contains_key(key) => {
+                symbol.element.properties.as_deref().unwrap_or_default()
+            }
+            _ => &[],
+        };
+
+        properties.iter().filter_map(move |property| {
+            let reference = property.parameter_ref.as_deref()?;
+            let held = self.resolve(symbol.domain, reference)?;
+            let (held_key, _) = self
+                .types
+                .get_key_value(&format!("{}.{}", held.domain, held.element.id))?;
+            if self.overrides.contains_key(held_key) {
+                return None;
+            }
+            Some((property.name.as_str(), held_key.as_str()))
+        })
+    }
+
+    /// The inline enums of `domain`, in the order they are declared.
+    pub fn inline_enums(&self, domain: &str) -> &[InlineEnum<'a>] {
+        self.inline_enums
+            .get(domain)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// The inline enum of `field` of the type, command or event `owner` in `domain`,
+    /// if it declares one.
+    pub fn inline_enum(
+        &self,
+        domain: &str,
+        owner: &str,
+        field: &Parameter,
+    ) -> Option<&InlineEnum<'a>> {
+        let values = field.parameter_enum.as_deref()?;
+        self.inline_enums(domain).iter().find(|shared| {
+            shared.field == field.name && shared.values == values && shared.owners.contains(&owner)
+        })
+    }
+
+    /// Whether the field `field` of the type `id` of `domain` is boxed to break a
+    /// cycle, see [`SymbolTable::find_cycles`].
+    pub fn is_boxed(&self, domain: &str, id: &str, field: &str) -> bool {
+        self.boxed
+            .contains(&(format!("{}.{}", domain, id), field.to_string()))
+    }
+
+    /// Resolves `reference` as written in `domain`, either `Type` or `Domain.Type`.
+    pub fn resolve(&self, domain: &str, reference: &str) -> Option<&Symbol<'a>> {
+        let (domain, id) = split_ref(domain, reference);
+        self.types.get(&format!("{}.{}", domain, id))
+    }
+
+    /// Whether `method` (`Domain.command`) is defined by any of the protocols.
+    pub fn has_command(&self, method: &str) -> bool {
+        self.commands.contains(method)
+    }
+
+    /// The other domains `domain` refers to, in name order.
+    pub fn dependencies(&self, domain: &str) -> impl Iterator<Item = &&'a str> {
+        self.dependencies.get(domain).into_iter().flatten()
+    }
+
+    /// Whether the type `reference` resolves to from `domain` is replaced by a Rust
+    /// type, and so isn't generated.
+    pub fn is_overridden(&self, domain: &str, reference: &str) -> bool {
+        self.resolve(domain, reference).is_some_and(|symbol| {
+            self.overrides
+                .contains_key(&format!("{}.{}", symbol.domain, symbol.element.id))
+        })
+    }
+
+    /// Path to the type `reference` resolves to, as seen from `scope` in `domain`.
+    /// Other domains are reached through the imports emitted for
+    /// [`SymbolTable::dependencies`], and overridden types by their own path.
+    pub fn path(&self, domain: &str, reference: &str, scope: Scope) -> TokenStream {
+        let symbol = self
+            .resolve(domain, reference)
+            .unwrap_or_else(|| panic!("unknown type `{}` in `{}`", reference, domain));
+        if let Some(path) = self
+            .overrides
+            .get(&format!("{}.{}", symbol.domain, symbol.element.id))
+        {
+            return path.clone();
+        }
+        let name = type_ident(&symbol.element.id);
+
+        let path = if symbol.domain == domain {
+            quote! { #name }
+        } else {
+            let dep = type_ident(symbol.domain);
+            quote! { #dep::#name }
+        };
+
+        match scope {
+            Scope::Domain => path,
+            Scope::Events => quote! { super::#path },
+        }
+    }
+}
+
+fn collect_items<'a>(
+    context: &str,
+    items: Option<&'a Items>,
+    references: &mut Vec<(String, &'a str)>,
+) {
+    if let Some(reference) = items.and_then(|items| items.items_ref.as_deref()) {
+        references.push((context.to_string(), reference));
+    }
+}
+
+fn collect_parameters<'a>(
+    context: &str,
+    parameters: Option<&'a [Parameter]>,
+    references: &mut Vec<(String, &'a str)>,
+) {
+    for parameter in parameters.unwrap_or_default() {
+        let context = format!("{}.{}", context, parameter.name);
+
+        if let Some(reference) = &parameter.parameter_ref {
+            references.push((context.clone(), reference));
+        }
+        collect_items(&context, parameter.items.as_ref(), references);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn protocol(json: &str) -> Protocol {
+        serde_json::from_str(json).unwrap()
+    }
+
+    fn overrides(overrides: &[(&str, &str)]) -> BTreeMap<String, String> {
+        overrides
+            .iter()
+            .map(|(id, path)| (id.to_string(), path.to_string()))
+            .collect()
+    }
+
+    const DOMAINS: &str = r#"{
+        "version": { "major": "1", "minor": "3" },
+        "domains": [
+            {
+                "domain": "DOM",
+                "types": [{ "id": "NodeId", "type": "integer" }],
+                "commands": []
+            },
+            {
+                "domain": "DOMDebugger",
+                "types": [
+                    { "id": "NodeId", "type": "string" },
+                    {
+                        "id": "EventListener",
+                        "type": "object",
+                        "properties": [
+                            { "name": "nodeId", "$ref": "DOM.NodeId" },
+                            { "name": "localId", "$ref": "NodeId" }
+                        ]
+                    }
+                ],
+                "commands": []
+            }
+        ]
+    }"#;
+
+    #[test]
+    fn resolves_references() {
+        let protocols = vec![protocol(DOMAINS)];
+        let symbols = SymbolTable::new(&protocols);
+
+        assert_eq!(
+            symbols.resolve("DOMDebugger", "DOM.NodeId").unwrap().domain,
+            "DOM"
+        );
+        assert_eq!(
+            symbols.resolve("DOMDebugger", "NodeId").unwrap().domain,
+            "DOMDebugger"
+        );
+        assert_eq!(
+            symbols.dependencies("DOMDebugger").collect::<Vec<_>>(),
+            vec![&"DOM"]
+        );
+        assert_eq!(
+            symbols
+                .path("DOMDebugger", "DOM.NodeId", Scope::Events)
+                .to_string(),
+            "super :: DOM :: NodeId"
+        );
+    }
+
+    #[test]
+    fn overrides_types() {
+        let protocols = vec![protocol(DOMAINS)];
+        let overrides = overrides(&[("DOM.NodeId", "crate::dom::NodeId")]);
+        let symbols = SymbolTable::new(&protocols).with_overrides(&overrides);
+
+        assert!(symbols.is_overridden("DOMDebugger", "DOM.NodeId"));
+        assert!(!symbols.is_overridden("DOMDebugger", "NodeId"));
+        assert_eq!(
+            symbols
+                .path("DOMDebugger", "DOM.NodeId", Scope::Events)
+                .to_string(),
+            "crate :: dom :: NodeId"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "`crate::Node` overrides unknown type `DOM.Node`")]
+    fn reports_unknown_overrides() {
+        let protocols = vec![protocol(DOMAINS)];
+        SymbolTable::new(&protocols).with_overrides(&overrides(&[("DOM.Node", "crate::Node")]));
+    }
+
+    #[test]
+    fn boxes_cycles() {
+        let protocols = vec![protocol(
+            r#"{
+                "version": { "major": "1", "minor": "3" },
+                "domains": [
+                    {
+                        "domain": "A",
+                        "types": [
+                            { "id": "Tree", "type": "object", "properties": [
+                                { "name": "children", "type": "array", "items": { "$ref": "Tree" } },
+                                { "name": "leaf", "$ref": "B.Leaf", "optional": true }
+                            ] }
+                        ],
+                        "commands": []
+                    },
+                    {
+                        "domain": "B",
+                        "types": [
+                            { "id": "Leaf", "type": "object", "properties": [
+                                { "name": "tree", "$ref": "A.Tree" },
+                                { "name": "next", "$ref": "Leaf", "optional": true }
+                            ] }
+                        ],
+                        "commands": []
+                    }
+                ]
+            }"#,
+        )];
+        let symbols = SymbolTable::new(&protocols);
+
+        assert!(!symbols.is_boxed("A", "Tree", "children"));
+        assert!(!symbols.is_boxed("A", "Tree", "leaf"));
+        assert!(symbols.is_boxed("B", "Leaf", "tree"));
+        assert!(symbols.is_boxed("B", "Leaf", "next"));
+
+        let symbols = symbols.with_overrides(&overrides(&[("A.Tree", "crate::Tree")]));
+        assert!(!symbols.is_boxed("B", "Leaf", "tree"));
+        assert!(symbols.is_boxed("B", "Leaf", "next"));
+    }
+
+    const ENUMS: &str = r#"{
+        "version": { "major": "1", "minor": "3" },
+        "domains": [{
+            "domain": "Page",
+            "types": [
+                { "id": "Frame", "type": "object", "properties": [
+                    { "name": "format", "type": "string", "enum": ["jpeg", "png"] }
+                ] }
+            ],
+            "commands": [
+                { "name": "captureScreenshot", "parameters": [
+                    { "name": "format", "type": "string", "enum": ["jpeg", "png"] },
+                    { "name": "mode", "type": "string", "enum": ["a", "b"] }
+                ] },
+                { "name": "startScreencast", "parameters": [
+                    { "name": "format", "type": "string", "enum": ["jpeg"] }
+                ] }
+            ],
+            "events": [
+                { "name": "frameNavigated", "parameters": [
+                    { "name": "mode", "type": "string", "enum": ["a", "b"] }
+                ] }
+            ]
+        }]
+    }"#;
+
+    #[test]
+    fn names_inline_enums() {
+        let protocols = vec![protocol(ENUMS)];
+        let symbols = SymbolTable::new(&protocols);
+
+        let names: Vec<(&str, &str)> = symbols
+            .inline_enums("Page")
+            .iter()
+            .map(|inline_enum| (inline_enum.name.as_str(), inline_enum.owner))
+            .collect();
+        assert_eq!(
+            names,
+            [
+                ("CaptureScreenshotFormat", "captureScreenshot"),
+                ("CaptureScreenshotMode", "captureScreenshot"),
+                ("FrameFormat", "Frame"),
+                ("FrameNavigatedMode", "frameNavigated"),
+                ("StartScreencastFormat", "startScreencast"),
+            ]
+        );
+
+        // events don't share the enums of commands
+        let event = &protocols[0].domains[0].events.as_deref().unwrap()[0];
+        let mode = &event.parameters.as_deref().unwrap()[0];
+        assert_eq!(
+            symbols
+                .inline_enum("Page", "frameNavigated", mode)
+                .unwrap()
+                .name,
+            "FrameNavigatedMode"
+        );
+    }
+
+    #[test]
+    fn names_shared_enums_in_any_order() {
+        let mut protocols = vec![protocol(
+            r#"{
+                "version": { "major": "1", "minor": "3" },
+                "domains": [{
+                    "domain": "Page",
+                    "commands": [
+                        { "name": "zoom", "parameters": [
+                            { "name": "mode", "type": "string", "enum": ["a", "b"] }
+                        ] },
+                        { "name": "capture", "parameters": [
+                            { "name": "mode", "type": "string", "enum": ["a", "b"] }
+                        ] }
+                    ],
+                    "events": [
+                        { "name": "zoomed", "parameters": [
+                            { "name": "mode", "type": "string", "enum": ["a", "b"] }
+                        ] }
+                    ]
+                }]
+            }"#,
+        )];
+        let names = |protocols: &[Protocol]| -> Vec<(String, Vec<String>)> {
+            SymbolTable::new(protocols)
+                .inline_enums("Page")
+                .iter()
+                .map(|inline_enum| (inline_enum.name.clone(), inline_enum.aliases.clone()))
+                .collect()
+        };
+
+        let expected = [
+            (String::from("CaptureMode"), vec![String::from("ZoomMode")]),
+            (String::from("ZoomedMode"), vec![]),
+        ];
+        assert_eq!(names(&protocols), expected);
+        protocols[0].domains[0].commands.reverse();
+        assert_eq!(names(&protocols), expected);
+    }
+
+    #[test]
+    fn keeps_enum_names_when_owners_are_added() {
+        let json = |commands: &[&str]| {
+            let commands: Vec<String> = commands
+                .iter()
+                .map(|name| {
+                    format!(
+                        r#"{{ "name": "{}", "parameters": [
+                            {{ "name": "mode", "type": "string", "enum": ["a", "b"] }}
+                        ] }}"#,
+                        name
+                    )
+                })
+                .collect();
+            format!(
+                r#"{{
+                    "version": {{ "major": "1", "minor": "3" }},
+                    "domains": [{{ "domain": "Page", "commands": [{}] }}]
+                }}"#,
+                commands.join(", ")
+            )
+        };
+        let names = |commands: &[&str]| -> Vec<String> {
+            let protocols = [protocol(&json(commands))];
+            let symbols = SymbolTable::new(&protocols);
+            let inline_enum = &symbols.inline_enums("Page")[0];
+            let mut names = inline_enum.aliases.clone();
+            names.push(inline_enum.name.clone());
+            names.sort();
+            names
+        };
+
+        assert_eq!(names(&["zoom"]), ["ZoomMode"]);
+        // `CaptureMode` comes first, and `ZoomMode` stays as an alias of it
+        assert_eq!(names(&["zoom", "capture"]), ["CaptureMode", "ZoomMode"]);
+        assert_eq!(
+            names(&["zoom", "capture", "annotate"]),
+            ["AnnotateMode", "CaptureMode", "ZoomMode"]
+        );
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "the type `Page.CaptureScreenshotMode` and the field `Page.captureScreenshot.mode` are both named `CaptureScreenshotMode`"
+    )]
+    fn reports_inline_enum_collisions() {
+        let json = ENUMS.replace(
+            r#""types": ["#,
+            r#""types": [{ "id": "CaptureScreenshotMode", "type": "string" },"#,
+        );
+        SymbolTable::new(&[protocol(&json)]);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "`DOMDebugger.EventListener.nodeId` refers to unknown type `DOM.Node`"
+    )]
+    fn reports_unknown_references() {
+        let protocols = vec![protocol(&DOMAINS.replace("DOM.NodeId", "DOM.Node"))];
+        SymbolTable::new(&protocols);
+    }
+}
@@ -0,0 +1,244 @@
+//! Wrappers of the protocol types holding times, which count seconds or
+//! milliseconds depending on the type, as [`Options::time_types`] generates them.
+
+use proc_macro2::{Ident, TokenStream};
+use quote::quote;
+
+use crate::attributes::Attributes;
+use crate::codegen::{Options, TimeCrate};
+use crate::ident::type_ident;
+use crate::types::{TypeElement, TypeEnum};
+
+/// What a time type counts.
+#[derive(Clone, Copy)]
+enum Kind {
+    /// Time since the Unix epoch.
+    SinceEpoch,
+    /// A duration, or time since an arbitrary point.
+    Duration,
+}
+
+/// The known time types by `Domain.Type`, with what they count and how many of
+/// their units make a second.
+const TIME_TYPES: [(&str, Kind, u32); 5] = [
+    ("Input.TimeSinceEpoch", Kind::SinceEpoch, 1),
+    ("Network.TimeSinceEpoch", Kind::SinceEpoch, 1),
+    ("Network.MonotonicTime", Kind::Duration, 1),
+    ("Runtime.Timestamp", Kind::SinceEpoch, 1000),
+    ("Runtime.TimeDelta", Kind::Duration, 1000),
+];
+
+/// The wrapper `element` of `domain` is generated as, `None` if it isn't a known
+/// time type or the time types are turned off.
+pub fn wrapper(
+    domain: &str,
+    element: &TypeElement,
+    attributes: &Attributes,
+    options: &Options,
+) -> Option<TokenStream> {
+    if !options.time_types || !matches!(element.type_type, TypeEnum::Number) {
+        return None;
+    }
+    let id = format!("{}.{}", domain, element.id);
+    let (_, kind, per_second) = TIME_TYPES.iter().find(|(time_type, ..)| *time_type == id)?;
+
+    let name = type_ident(&element.id);
+    let unit = if *per_second == 1 {
+        "Seconds"
+    } else {
+        "Milliseconds"
+    };
+    let per_second = f64::from(*per_second);
+    let attributes = attributes.of_type(domain, &element.id);
+
+    let (doc, conversions) = match kind {
+        Kind::SinceEpoch => (
+            format!("{} since the Unix epoch.", unit),
+            since_epoch(&name, per_second, &options.time_crates),
+        ),
+        Kind::Duration => (
+            format!("{}, as a duration.", unit),
+            duration(&name, per_second, &options.time_crates),
+        ),
+    };
+
+    Some(quote! {
+        #[doc = #doc]
+        #attributes
+        #[serde(transparent)]
+        pub struct #name(pub JsFloat);
+
+        #conversions
+    })
+}
+
+fn since_epoch(name: &Ident, per_second: f64, time_crates: &[TimeCrate]) -> TokenStream {
+    let chrono = time_crates.contains(&TimeCrate::Chrono).then(|| {
+        quote! {
+            impl #name {
+                /// The time as a `chrono::DateTime`, `None` if it can't represent it.
+                pub fn to_chrono(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+                    let seconds = self.0 / #per_second;
+                    if !(seconds.is_finite() && seconds.abs() < i64::MAX as f64) {
+                        return None;
+                    }
+                    let whole = seconds.floor();
+                    let nanos = (((seconds - whole) * 1e9) as u32).min(999_999_999);
+                    chrono::TimeZone::timestamp_opt(&chrono::Utc, whole as i64, nanos).single()
+                }
+            }
+
+            impl From<chrono::DateTime<chrono::Utc>> for #name {
+                fn from(time: chrono::DateTime<chrono::Utc>) -> Self {
+                    let seconds = time.timestamp() as f64 + f64::from(time.timestamp_subsec_nanos()) / 1e9;
+                    #name(seconds * #per_second)
+                }
+            }
+        }
+    });
+    let time = time_crates.contains(&TimeCrate::Time).then(|| {
+        quote! {
+            impl #name {
+                /// The time as a `time::OffsetDateTime`, `None` if it can't represent it.
+                pub fn to_offset_date_time(&self) -> Option<time::OffsetDateTime> {
+                    let nanos = self.0 / #per_second * 1e9;
+                    if !(nanos.is_finite() && nanos.abs() < i128::MAX as f64) {
+                        return None;
+                    }
+                    time::OffsetDateTime::from_unix_timestamp_nanos(nanos as i128).ok()
+                }
+            }
+
+            impl From<time::OffsetDateTime> for #name {
+                fn from(time: time::OffsetDateTime) -> Self {
+                    #name(time.unix_timestamp_nanos() as f64 / 1e9 * #per_second)
+                }
+            }
+        }
+    });
+
+    quote! {
+        impl #name {
+            /// The time as a `SystemTime`, `None` if it can't represent it.
+            pub fn to_system_time(&self) -> Option<std::time::SystemTime> {
+                let seconds = self.0 / #per_second;
+                if !(seconds.is_finite() && seconds.abs() < u64::MAX as f64) {
+                    return None;
+                }
+                let since_epoch = std::time::Duration::from_secs_f64(seconds.abs());
+                if seconds >= 0.0 {
+                    std::time::UNIX_EPOCH.checked_add(since_epoch)
+                } else {
+                    std::time::UNIX_EPOCH.checked_sub(since_epoch)
+                }
+            }
+        }
+
+        impl From<std::time::SystemTime> for #name {
+            fn from(time: std::time::SystemTime) -> Self {
+                let seconds = match time.duration_since(std::time::UNIX_EPOCH) {
+                    Ok(since_epoch) => since_epoch.as_secs_f64(),
+                    Err(before_epoch) => -before_epoch.duration().as_secs_f64(),
+                };
+                #name(seconds * #per_second)
+            }
+        }
+
+        #chrono
+
+        #time
+    }
+}
+
+fn duration(name: &Ident, per_second: f64, time_crates: &[TimeCrate]) -> TokenStream {
+    let chrono = time_crates.contains(&TimeCrate::Chrono).then(|| {
+        quote! {
+            impl #name {
+                /// The duration as a `chrono::Duration`, `None` if it can't represent it.
+                pub fn to_chrono_duration(&self) -> Option<chrono::Duration> {
+                    chrono::Duration::from_std(self.to_duration()?).ok()
+                }
+            }
+        }
+    });
+    let time = time_crates.contains(&TimeCrate::Time).then(|| {
+        quote! {
+            impl #name {
+                /// The duration as a `time::Duration`, `None` if it can't represent it.
+                pub fn to_time_duration(&self) -> Option<time::Duration> {
+                    std::convert::TryFrom::try_from(self.to_duration()?).ok()
+                }
+            }
+        }
+    });
+
+    quote! {
+        impl #name {
+            /// The duration as a `std::time::Duration`, `None` if it is negative or too long.
+            pub fn to_duration(&self) -> Option<std::time::Duration> {
+                let seconds = self.0 / #per_second;
+                if seconds.is_finite() && seconds >= 0.0 && seconds < u64::MAX as f64 {
+                    Some(std::time::Duration::from_secs_f64(seconds))
+                } else {
+                    None
+                }
+            }
+        }
+
+        impl From<std::time::Duration> for #name {
+            fn from(duration: std::time::Duration) -> Self {
+                #name(duration.as_secs_f64() * #per_second)
+            }
+        }
+
+        #chrono
+
+        #time
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::symbols::SymbolTable;
+    use crate::types::Protocol;
+
+    #[test]
+    fn wraps_known_time_types() {
+        let protocols = [Protocol::from_json(
+            r#"{
+                "version": { "major": "1", "minor": "3" },
+                "domains": [{
+                    "domain": "Runtime",
+                    "types": [
+                        { "id": "Timestamp", "type": "number" },
+                        { "id": "TimeDelta", "type": "number" },
+                        { "id": "ExecutionContextId", "type": "integer" }
+                    ],
+                    "commands": []
+                }]
+            }"#,
+        )
+        .unwrap()];
+        let symbols = SymbolTable::new(&protocols);
+        let options = Options::new().time_types(true);
+        let attributes = Attributes::new(&protocols, &symbols, &options);
+        let types = protocols[0].domains[0].types.as_deref().unwrap();
+
+        let timestamp = wrapper("Runtime", &types[0], &attributes, &options)
+            .unwrap()
+            .to_string();
+        assert!(timestamp.contains("pub struct Timestamp (pub JsFloat)"));
+        assert!(timestamp.contains("fn to_system_time"));
+        assert!(timestamp.contains("1000f64"));
+        assert!(!timestamp.contains("chrono"));
+
+        let delta = wrapper("Runtime", &types[1], &attributes, &options)
+            .unwrap()
+            .to_string();
+        assert!(delta.contains("fn to_duration"));
+
+        assert!(wrapper("Runtime", &types[2], &attributes, &options).is_none());
+        assert!(wrapper("Runtime", &types[0], &attributes, &Options::new()).is_none());
+    }
+}
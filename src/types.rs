@@ -1,7 +1,31 @@
+//! The protocol model, as `js_protocol.json` and `browser_protocol.json` define it.
+//!
+//! References between types are kept as written: `Type` within the same domain or
+//! `Domain.Type` across domains, which [`Protocol::resolve_ref`] looks up.
+//!
+//! The structs are `#[non_exhaustive]` so that fields the protocol gains can be
+//! added to them. They are read with [`Protocol::from_json`] or built with their
+//! `new` constructors, which leave every optional field `None`, and their fields
+//! can be changed to patch a protocol:
+//!
+//! ```
+//! use auto_generate_cdp::types::{Command, Domain, Parameter, Protocol, TypeEnum, Version};
+//!
+//! let mut command = Command::new("setEnabled");
+//! command.parameters = Some(vec![Parameter::of_type("enabled", TypeEnum::Boolean)]);
+//! let mut domain = Domain::new("Custom");
+//! domain.commands.push(command);
+//! let protocol = Protocol::new(Version::new("1", "3"), vec![domain]);
+//! assert!(protocol.domain("Custom").unwrap().find_command("setEnabled").is_some());
+//! ```
+
 extern crate serde_json;
 
 use serde::{Deserialize, Serialize};
-#[derive(Serialize, Deserialize, Debug)]
+
+/// A protocol file.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[non_exhaustive]
 pub struct Protocol {
     #[serde(rename = "version")]
     pub version: Version,
@@ -10,7 +34,9 @@ pub struct Protocol {
     pub domains: Vec<Domain>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+/// A domain, e.g. `Page`, with its types, commands and events.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[non_exhaustive]
 pub struct Domain {
     #[serde(rename = "domain")]
     pub domain: String,
@@ -34,7 +60,9 @@ pub struct Domain {
     pub description: Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+/// A command, called as `Domain.name`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[non_exhaustive]
 pub struct Command {
     #[serde(rename = "name")]
     pub name: String,
@@ -58,7 +86,10 @@ pub struct Command {
     pub redirect: Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+/// A parameter or return value of a command, a parameter of an event or a
+/// property of an object type.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[non_exhaustive]
 pub struct Parameter {
     #[serde(rename = "name")]
     pub name: String,
@@ -88,7 +119,9 @@ pub struct Parameter {
     pub deprecated: Option<bool>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+/// The element type of an array.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[non_exhaustive]
 pub struct Items {
     #[serde(rename = "type")]
     pub items_type: Option<TypeEnum>,
@@ -97,7 +130,9 @@ pub struct Items {
     pub items_ref: Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+/// An event, sent as `Domain.name`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[non_exhaustive]
 pub struct Event {
     #[serde(rename = "name")]
     pub name: String,
@@ -115,7 +150,9 @@ pub struct Event {
     pub deprecated: Option<bool>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+/// A type defined by a domain, referred to by its `id`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[non_exhaustive]
 pub struct TypeElement {
     #[serde(rename = "id")]
     pub id: String,
@@ -142,7 +179,9 @@ pub struct TypeElement {
     pub deprecated: Option<bool>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+/// The protocol version.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[non_exhaustive]
 pub struct Version {
     #[serde(rename = "major")]
     pub major: String,
@@ -151,7 +190,8 @@ pub struct Version {
     pub minor: String,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+/// A JSON type.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TypeEnum {
     #[serde(rename = "any")]
     Any,
@@ -174,3 +214,264 @@ pub enum TypeEnum {
     #[serde(rename = "string")]
     String,
 }
+
+impl Protocol {
+    /// A protocol with the given domains.
+    pub fn new(version: Version, domains: Vec<Domain>) -> Self {
+        Protocol { version, domains }
+    }
+
+    /// Parses a protocol file.
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+
+    /// The domain called `name`.
+    pub fn domain(&self, name: &str) -> Option<&Domain> {
+        self.domains.iter().find(|domain| domain.domain == name)
+    }
+
+    /// Every command with the domain it belongs to.
+    pub fn commands(&self) -> impl Iterator<Item = (&Domain, &Command)> {
+        self.domains
+            .iter()
+            .flat_map(|domain| domain.commands.iter().map(move |command| (domain, command)))
+    }
+
+    /// Every event with the domain it belongs to.
+    pub fn events(&self) -> impl Iterator<Item = (&Domain, &Event)> {
+        self.domains.iter().flat_map(|domain| {
+            domain
+                .events
+                .iter()
+                .flatten()
+                .map(move |event| (domain, event))
+        })
+    }
+
+    /// The type `reference` refers to from within `domain`, with the domain that
+    /// defines it.
+    pub fn resolve_ref(&self, domain: &str, reference: &str) -> Option<(&Domain, &TypeElement)> {
+        let (domain, id) = split_ref(domain, reference);
+        let domain = self.domain(domain)?;
+
+        Some((domain, domain.find_type(id)?))
+    }
+}
+
+/// The domain and id of the type `reference` refers to from within `domain`, as
+/// `reference` is either `Type` or `Domain.Type`.
+pub(crate) fn split_ref<'r>(domain: &'r str, reference: &'r str) -> (&'r str, &'r str) {
+    reference.split_once('.').unwrap_or((domain, reference))
+}
+
+impl Version {
+    /// The version `major.minor`.
+    pub fn new(major: impl Into<String>, minor: impl Into<String>) -> Self {
+        Version {
+            major: major.into(),
+            minor: minor.into(),
+        }
+    }
+}
+
+impl Domain {
+    /// A domain called `domain` without types, commands or events.
+    pub fn new(domain: impl Into<String>) -> Self {
+        Domain {
+            domain: domain.into(),
+            experimental: None,
+            dependencies: None,
+            types: None,
+            commands: Vec::new(),
+            events: None,
+            description: None,
+        }
+    }
+
+    /// The type with the id `id`.
+    pub fn find_type(&self, id: &str) -> Option<&TypeElement> {
+        self.types.iter().flatten().find(|element| element.id == id)
+    }
+
+    /// The command called `name`.
+    pub fn find_command(&self, name: &str) -> Option<&Command> {
+        self.commands.iter().find(|command| command.name == name)
+    }
+
+    /// The event called `name`.
+    pub fn find_event(&self, name: &str) -> Option<&Event> {
+        self.events
+            .iter()
+            .flatten()
+            .find(|event| event.name == name)
+    }
+}
+
+impl Command {
+    /// A command called `name` without parameters or return values.
+    pub fn new(name: impl Into<String>) -> Self {
+        Command {
+            name: name.into(),
+            description: None,
+            experimental: None,
+            parameters: None,
+            returns: None,
+            deprecated: None,
+            redirect: None,
+        }
+    }
+}
+
+impl Event {
+    /// An event called `name` without parameters.
+    pub fn new(name: impl Into<String>) -> Self {
+        Event {
+            name: name.into(),
+            description: None,
+            parameters: None,
+            experimental: None,
+            deprecated: None,
+        }
+    }
+}
+
+impl Parameter {
+    /// A required parameter called `name` of the JSON type `parameter_type`.
+    pub fn of_type(name: impl Into<String>, parameter_type: TypeEnum) -> Self {
+        Parameter {
+            parameter_type: Some(parameter_type),
+            ..Parameter::named(name.into())
+        }
+    }
+
+    /// A required parameter called `name` of the type `reference` refers to.
+    pub fn of_ref(name: impl Into<String>, reference: impl Into<String>) -> Self {
+        Parameter {
+            parameter_ref: Some(reference.into()),
+            ..Parameter::named(name.into())
+        }
+    }
+
+    fn named(name: String) -> Self {
+        Parameter {
+            name,
+            description: None,
+            optional: None,
+            parameter_ref: None,
+            parameter_type: None,
+            items: None,
+            parameter_enum: None,
+            experimental: None,
+            deprecated: None,
+        }
+    }
+}
+
+impl Items {
+    /// Elements of the JSON type `items_type`.
+    pub fn of_type(items_type: TypeEnum) -> Self {
+        Items {
+            items_type: Some(items_type),
+            items_ref: None,
+        }
+    }
+
+    /// Elements of the type `reference` refers to.
+    pub fn of_ref(reference: impl Into<String>) -> Self {
+        Items {
+            items_type: None,
+            items_ref: Some(reference.into()),
+        }
+    }
+}
+
+impl TypeElement {
+    /// A type with the id `id` of the JSON type `type_type`.
+    pub fn new(id: impl Into<String>, type_type: TypeEnum) -> Self {
+        TypeElement {
+            id: id.into(),
+            description: None,
+            type_type,
+            type_enum: None,
+            properties: None,
+            experimental: None,
+            items: None,
+            deprecated: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_refs() {
+        let protocol = Protocol::from_json(
+            r#"{
+                "version": { "major": "1", "minor": "3" },
+                "domains": [
+                    { "domain": "Network", "types": [{ "id": "LoaderId", "type": "string" }],
+                      "commands": [{ "name": "enable" }] },
+                    { "domain": "Page", "types": [{ "id": "FrameId", "type": "string" }],
+                      "commands": [{ "name": "enable" }, { "name": "navigate" }],
+                      "events": [{ "name": "loadEventFired" }] }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let (domain, element) = protocol.resolve_ref("Page", "Network.LoaderId").unwrap();
+        assert_eq!((&*domain.domain, &*element.id), ("Network", "LoaderId"));
+        let (domain, element) = protocol.resolve_ref("Page", "FrameId").unwrap();
+        assert_eq!((&*domain.domain, &*element.id), ("Page", "FrameId"));
+        assert!(protocol.resolve_ref("Network", "FrameId").is_none());
+
+        let commands: Vec<_> = protocol
+            .commands()
+            .map(|(domain, command)| format!("{}.{}", domain.domain, command.name))
+            .collect();
+        assert_eq!(commands, ["Network.enable", "Page.enable", "Page.navigate"]);
+        assert_eq!(protocol.events().count(), 1);
+        assert!(protocol
+            .domain("Page")
+            .unwrap()
+            .find_command("navigate")
+            .is_some());
+    }
+
+    #[test]
+    fn builds_protocols() {
+        let parsed = Protocol::from_json(
+            r#"{
+                "version": { "major": "1", "minor": "3" },
+                "domains": [
+                    { "domain": "Page",
+                      "types": [{ "id": "FrameId", "type": "string" }],
+                      "commands": [{ "name": "getFrames", "returns": [
+                          { "name": "frameIds", "type": "array", "items": { "$ref": "FrameId" } }
+                      ] }],
+                      "events": [{ "name": "frameAttached", "parameters": [
+                          { "name": "frameId", "$ref": "FrameId" }
+                      ] }] }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let mut frame_ids = Parameter::of_type("frameIds", TypeEnum::Array);
+        frame_ids.items = Some(Items::of_ref("FrameId"));
+        let mut command = Command::new("getFrames");
+        command.returns = Some(vec![frame_ids]);
+        let mut event = Event::new("frameAttached");
+        event.parameters = Some(vec![Parameter::of_ref("frameId", "FrameId")]);
+        let mut domain = Domain::new("Page");
+        domain.types = Some(vec![TypeElement::new("FrameId", TypeEnum::String)]);
+        domain.commands.push(command);
+        domain.events = Some(vec![event]);
+        let built = Protocol::new(Version::new("1", "3"), vec![domain]);
+
+        assert_eq!(built, parsed);
+    }
+}